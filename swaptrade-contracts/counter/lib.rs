@@ -48,7 +48,7 @@ impl CounterContract {
         portfolio.balance_of(&env, asset, user)
     }
 
-    /// Swap tokens for a user using a simplified AMM (1:1 XLM <-> USDC-SIM)
+    /// Swap tokens for a user through the constant-product pool (XLM <-> USDC-SIM)
     /// - Validates input tokens
     /// - Checks sufficient funds
     /// - Debits from `from` and credits to `to`
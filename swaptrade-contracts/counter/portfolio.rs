@@ -1,5 +1,6 @@
 extern crate alloc;
-use soroban_sdk::{contracttype, Address, Env, Symbol, Map, Vec};
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol, Map, Vec};
+use crate::rate_limit::RateLimiter;
 #[cfg(test)]
 use soroban_sdk::testutils::Address as TestAddress;
 
@@ -30,6 +31,57 @@ pub enum Badge {
     
     /// Trade consistently across blocks - achieved when trading on 7+ different ledger heights
     Consistency,
+
+    // Variants are appended so existing badge data keeps decoding after upgrades
+
+    /// Seasoned trader - achieved at 100+ trades
+    VeteranTrader,
+
+    /// Trading master - achieved at 1000+ trades
+    MasterTrader,
+
+    /// Lottery bonus - granted at random by `maybe_award_bonus`
+    LuckyDraw,
+
+    /// Early adopter - among the first `early_adopter_cutoff` unique traders
+    EarlyAdopter,
+}
+
+/// How a user's PnL is reported by `get_portfolio` and the leaderboard
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[contracttype]
+pub enum PnlMode {
+    /// Net balance change from mints and debits
+    BalanceDelta,
+    /// Only swap gains and losses (output minus input)
+    RealizedOnly,
+}
+
+/// Every badge in enum declaration order; keep in sync when appending a variant
+const ALL_BADGES: [Badge; 10] = [
+    Badge::FirstTrade,
+    Badge::Trader,
+    Badge::WealthBuilder,
+    Badge::LiquidityProvider,
+    Badge::Diversifier,
+    Badge::Consistency,
+    Badge::VeteranTrader,
+    Badge::MasterTrader,
+    Badge::LuckyDraw,
+    Badge::EarlyAdopter,
+];
+
+/// Trade-count badges in ascending threshold order
+const TRADE_COUNT_BADGES: [Badge; 3] = [Badge::Trader, Badge::VeteranTrader, Badge::MasterTrader];
+
+/// Default trade count needed for a trade-count badge (0 for other badges)
+fn default_badge_threshold(badge: &Badge) -> u32 {
+    match badge {
+        Badge::Trader => 10,
+        Badge::VeteranTrader => 100,
+        Badge::MasterTrader => 1000,
+        _ => 0,
+    }
 }
 
 #[derive(Clone)]
@@ -38,31 +90,102 @@ pub struct Portfolio {
     balances: Map<(Address, Asset), i128>,
     trades: Map<Address, u32>,       // number of trades per user
     pnl: Map<Address, i128>,         // cumulative balance change placeholder
+    realized_pnl: Map<Address, i128>, // cumulative swap output minus input, both valued in the stable asset
+    pnl_mode: PnlMode,               // which PnL figure is reported
     badges: Map<(Address, Badge), bool>, // tracks which badges each user has earned
+    badge_award_times: Map<(Address, Badge), u64>, // ledger timestamp each badge was earned
+    early_adopter_cutoff: u32,        // first N unique traders earn EarlyAdopter (0 = disabled)
+    badge_thresholds: Map<Badge, u32>, // admin overrides of trade-count badge thresholds
+    badge_fee_discounts: Map<Badge, u32>, // swap fee discount (bps) granted by holding a badge
+    defer_badge_checks: bool,         // skip inline badge checks; awarded via sync_badges instead
     metrics: Metrics,                 // lightweight aggregate metrics
     
     // Admin Dashboard Aggregate Stats
     total_users: u32,                 // unique traders/LPs
     total_trading_volume: i128,       // sum of all swap amounts
+    lifetime_trading_volume: i128,    // sum of all swap amounts; never archived
+    volume_epoch: u32,                // times total_trading_volume has been archived and reset
+    global_volume_cap: i128,          // swaps stop once capped_volume would pass this (0 = no cap)
+    capped_volume: i128,              // gross swap input counted against the cap; never archived
     active_users: Vec<Address>,       // users with activity (limited to last N blocks)
+    daily_active_day: u64,            // UTC day number (timestamp / SECONDS_PER_DAY) `daily_active_users` belongs to
+    daily_active_users: Map<Address, bool>, // users who traded during `daily_active_day`
     top_traders: Vec<(Address, i128)>, // top 100 traders by PnL
     xlm_in_pool: i128,               // liquidity pool XLM
     usdc_in_pool: i128,              // liquidity pool USDC
     total_fees_collected: i128,       // accumulated fees
+    protocol_fees: Map<Asset, i128>,  // swap fees held by the protocol, per asset they were paid in
+    fee_checkpoints: Vec<(u64, i128)>, // (timestamp, cumulative fees), oldest first
+    trade_checkpoints: Vec<(u64, u32)>, // (timestamp, cumulative trades executed), oldest first
+    pair_fees: Map<(Symbol, Symbol), i128>, // swap fees per canonical (ordered) pair
+    rate_decimals: Map<(Symbol, Symbol), u32>, // `rate_achieved` decimals per canonical pair, when not the default
+    utilization_history: Vec<(u64, u32)>, // (window start, utilization bps), oldest first
+    utilization_window_volume: i128,      // swap volume accrued in the latest window
+    fees_paid: Map<Address, i128>,    // lifetime swap fees paid per user
+    total_supply: Map<Asset, i128>,   // issued supply per asset (mint minus burn)
+    xlm_to_usdc_volume: i128,         // cumulative XLM sold into the pool
+    usdc_to_xlm_volume: i128,         // cumulative USDCSIM sold into the pool
+    user_directional_volume: Map<Address, (i128, i128)>, // per-user (XLM->USDCSIM, USDCSIM->XLM) input volume
     
     // Badge & Achievement Tracking
     initial_balances: Map<Address, i128>,  // starting balance for WealthBuilder tracking
     token_pairs_traded: Map<Address, Vec<Symbol>>, // unique token pairs per user
     ledger_heights_traded: Map<Address, Vec<u64>>, // ledger heights where user traded
     lp_deposits_count: Map<Address, u32>,  // number of LP deposits per user
+    lp_deposit_history: Map<Address, Vec<LPDepositRecord>>, // recent LP deposits per user, oldest first
     transactions: Map<Address, Vec<Transaction>>, // transaction history
+    failed_swaps: Map<Address, Vec<FailedSwap>>, // rejected try_swap attempts, oldest first
+    last_swaps: Map<Address, SwapDetails>, // each user's most recent swap, for auditing
+    largest_trade: Option<(Address, i128)>, // biggest swap by input amount and who made it
+    first_activity_time: Option<u64>,      // ledger timestamp of the first mint or swap
+    activity_log: Map<Address, Vec<ActivityEvent>>, // LP actions and badge awards (trades live in `transactions`)
+    tx_history_cap: u32,                   // max transactions kept per user (oldest evicted first)
+    max_tx_return: u32,                    // max transactions returned by one history query
 
     // LP Position Tracking
     lp_positions: Map<Address, LPPosition>, // LP positions per user
+    lp_providers: Vec<Address>,            // every address that has held an LP position
+    active_lp_count: u32,                  // providers currently holding LP tokens
     total_lp_tokens: i128,                 // total LP tokens minted (for share calculations)
-    lp_fees_accumulated: i128,            // accumulated fees for LP distribution
+    protocol_lp_tokens: i128,              // LP tokens owned by the protocol from seeding and compounded fees
+    protocol_lp_cost: i128,                // stable-asset value the protocol put in for its LP tokens
+    protocol_fees_compounded: i128,        // stable-asset value of protocol fees reinvested into the pool
+    lp_fees_accumulated: Map<Asset, i128>, // fees awaiting LP distribution, per asset they were paid in
+    lp_deposit_times: Map<Address, u64>,   // timestamp of each LP's most recent deposit
+    lp_min_hold_seconds: u64,              // minimum holding time before a position earns fees
+    lp_exit_fee_bps: u32,                  // fee withheld from LP withdrawals, left in the pool
+    min_lp_deposit: i128,                  // smallest LP deposit accepted, valued in the stable asset
+    max_positions_per_user: Option<u32>,   // most LP positions one user may hold (None = unlimited)
+    stable_symbol: Symbol,                 // symbol of the pool's stable asset (USDCSIM by default)
 }
 
+/// Default number of transactions retained per user
+pub const DEFAULT_TX_HISTORY_CAP: u32 = 100;
+
+/// Default maximum number of transactions returned by one history query
+pub const DEFAULT_MAX_TX_RETURN: u32 = 100;
+
+/// Maximum number of fee revenue checkpoints retained
+pub const MAX_FEE_CHECKPOINTS: u32 = 100;
+
+/// Maximum number of global trade count checkpoints retained
+pub const MAX_TRADE_CHECKPOINTS: u32 = 100;
+
+/// Maximum number of utilization windows retained
+pub const MAX_UTILIZATION_WINDOWS: u32 = 100;
+
+/// Length of a utilization window in seconds
+pub const UTILIZATION_WINDOW_SECONDS: u64 = 3_600;
+
+/// Length of a UTC day in ledger seconds, for daily active user tracking
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Default number of decimals in `Transaction::rate_achieved`
+pub const DEFAULT_RATE_DECIMALS: u32 = 7;
+
+/// Upper bound on a pair's configured rate decimals
+pub const MAX_RATE_DECIMALS: u32 = 18;
+
 #[derive(Clone, Debug, PartialEq)] // Added derives for testing
 #[contracttype]
 pub struct Transaction {
@@ -71,8 +194,86 @@ pub struct Transaction {
     pub to_token: Symbol,
     pub from_amount: i128,
     pub to_amount: i128,
-    pub rate_achieved: u128, // Represented with 7 decimals precision (units of 10^-7)
+    pub rate_achieved: u128, // Fixed point with the pair's rate decimals (7 by default, units of 10^-7)
     pub migration_time: Option<u64>,      // Timestamp when V2 migration occurred
+    pub recipient: Option<Address>,       // Address credited with `to_amount` when routed away from the user
+}
+
+/// Pool reserves around a user's swap, kept for auditing
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct SwapDetails {
+    pub timestamp: u64,
+    pub from_token: Symbol,
+    pub to_token: Symbol,
+    pub amount_in: i128,
+    pub fee_amount: i128,
+    pub amount_out: i128,
+    /// Address credited with `amount_out`: the swapper, unless the output was routed elsewhere
+    pub recipient: Address,
+    /// (XLM, stable) reserves before the swap
+    pub reserves_before: (i128, i128),
+    /// (XLM, stable) reserves after the swap
+    pub reserves_after: (i128, i128),
+}
+
+/// Market-wide swap summary
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct SwapStats {
+    pub total_swaps: u32,
+    pub total_volume: i128,
+    pub total_fees: i128,
+    /// Lifetime volume / total_swaps, or 0 before the first swap. Unlike `total_volume` it
+    /// isn't reset by archiving, since the swap count isn't either.
+    pub average_swap_size: i128,
+    pub failed_orders: u32,
+}
+
+/// One entry in a user's activity timeline
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum ActivityEvent {
+    /// A completed swap
+    Trade(Transaction),
+    /// (timestamp, xlm_amount, usdc_amount)
+    LiquidityAdded(u64, i128, i128),
+    /// (timestamp, xlm_amount, usdc_amount)
+    LiquidityRemoved(u64, i128, i128),
+    /// (timestamp, badge)
+    BadgeEarned(u64, Badge),
+}
+
+impl ActivityEvent {
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            ActivityEvent::Trade(tx) => tx.timestamp,
+            ActivityEvent::LiquidityAdded(ts, _, _) => *ts,
+            ActivityEvent::LiquidityRemoved(ts, _, _) => *ts,
+            ActivityEvent::BadgeEarned(ts, _) => *ts,
+        }
+    }
+}
+
+/// A swap attempt rejected by `try_swap`, kept for debugging
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct FailedSwap {
+    pub timestamp: u64,
+    pub from_token: Symbol,
+    pub to_token: Symbol,
+    pub amount: i128,
+    pub reason: OrderFailure,
+}
+
+/// One LP deposit, as received by the pool
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct LPDepositRecord {
+    pub timestamp: u64,
+    pub xlm_amount: i128,
+    pub usdc_amount: i128,
+    pub lp_tokens_minted: i128,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -82,6 +283,8 @@ pub struct LPPosition {
     pub xlm_deposited: i128,
     pub usdc_deposited: i128,
     pub lp_tokens_minted: i128,
+    /// Pool rate at entry (USDCSIM per XLM, 1e7 scale), weighted by LP tokens across deposits
+    pub entry_price: u128,
 }
 
 impl Portfolio {
@@ -90,27 +293,147 @@ impl Portfolio {
             balances: Map::new(env),
             trades: Map::new(env),
             pnl: Map::new(env),
+            realized_pnl: Map::new(env),
+            pnl_mode: PnlMode::BalanceDelta,
             badges: Map::new(env),
+            badge_award_times: Map::new(env),
+            early_adopter_cutoff: 0,
+            badge_thresholds: Map::new(env),
+            badge_fee_discounts: Map::new(env),
+            defer_badge_checks: false,
             metrics: Metrics::default(),
             total_users: 0,
             total_trading_volume: 0,
+            lifetime_trading_volume: 0,
+            volume_epoch: 0,
+            global_volume_cap: 0,
+            capped_volume: 0,
             active_users: Vec::new(env),
+            daily_active_day: 0,
+            daily_active_users: Map::new(env),
             top_traders: Vec::new(env),
             xlm_in_pool: 0,
             usdc_in_pool: 0,
             total_fees_collected: 0,
+            protocol_fees: Map::new(env),
+            fee_checkpoints: Vec::new(env),
+            trade_checkpoints: Vec::new(env),
+            pair_fees: Map::new(env),
+            rate_decimals: Map::new(env),
+            utilization_history: Vec::new(env),
+            utilization_window_volume: 0,
+            fees_paid: Map::new(env),
+            total_supply: Map::new(env),
+            xlm_to_usdc_volume: 0,
+            usdc_to_xlm_volume: 0,
+            user_directional_volume: Map::new(env),
             initial_balances: Map::new(env),
             token_pairs_traded: Map::new(env),
             ledger_heights_traded: Map::new(env),
             lp_deposits_count: Map::new(env),
+            lp_deposit_history: Map::new(env),
             transactions: Map::new(env),
+            failed_swaps: Map::new(env),
+            last_swaps: Map::new(env),
+            largest_trade: None,
+            first_activity_time: None,
+            activity_log: Map::new(env),
+            tx_history_cap: DEFAULT_TX_HISTORY_CAP,
+            max_tx_return: DEFAULT_MAX_TX_RETURN,
             lp_positions: Map::new(env),
+            lp_providers: Vec::new(env),
+            active_lp_count: 0,
             total_lp_tokens: 0,
-            lp_fees_accumulated: 0,
+            protocol_lp_tokens: 0,
+            protocol_lp_cost: 0,
+            protocol_fees_compounded: 0,
+            lp_fees_accumulated: Map::new(env),
+            lp_deposit_times: Map::new(env),
+            lp_min_hold_seconds: 0,
+            lp_exit_fee_bps: 0,
+            min_lp_deposit: 1,
+            max_positions_per_user: None,
+            stable_symbol: symbol_short!("USDCSIM"),
             migration_time: None,
         }
     }
 
+    /// Rebuild a V1 portfolio in the current layout. Fields V1 didn't have start at their
+    /// defaults, except those derivable from V1 data: LP providers and entry prices, and
+    /// issued supply (balances plus pool reserves).
+    pub fn from_v1(env: &Env, v1: crate::migration::PortfolioV1) -> Self {
+        let mut portfolio = Portfolio::new(env);
+        portfolio.balances = v1.balances;
+        portfolio.trades = v1.trades;
+        portfolio.pnl = v1.pnl;
+        portfolio.badges = v1.badges;
+        portfolio.metrics = Metrics {
+            trades_executed: v1.metrics.trades_executed,
+            failed_orders: v1.metrics.failed_orders,
+            balances_updated: v1.metrics.balances_updated,
+            ..Metrics::default()
+        };
+        portfolio.total_users = v1.total_users;
+        portfolio.total_trading_volume = v1.total_trading_volume;
+        portfolio.lifetime_trading_volume = v1.total_trading_volume;
+        portfolio.active_users = v1.active_users;
+        portfolio.top_traders = v1.top_traders;
+        portfolio.xlm_in_pool = v1.xlm_in_pool;
+        portfolio.usdc_in_pool = v1.usdc_in_pool;
+        portfolio.total_fees_collected = v1.total_fees_collected;
+        portfolio.initial_balances = v1.initial_balances;
+        portfolio.token_pairs_traded = v1.token_pairs_traded;
+        portfolio.ledger_heights_traded = v1.ledger_heights_traded;
+        portfolio.lp_deposits_count = v1.lp_deposits_count;
+        for (user, history) in v1.transactions.iter() {
+            let mut converted = Vec::new(env);
+            for tx in history.iter() {
+                converted.push_back(Transaction {
+                    timestamp: tx.timestamp,
+                    from_token: tx.from_token,
+                    to_token: tx.to_token,
+                    from_amount: tx.from_amount,
+                    to_amount: tx.to_amount,
+                    rate_achieved: tx.rate_achieved,
+                    migration_time: tx.migration_time,
+                    recipient: None,
+                });
+            }
+            portfolio.transactions.set(user, converted);
+        }
+        portfolio.total_lp_tokens = v1.total_lp_tokens;
+        // V1 kept LP fees as one total without recording the asset; they carry over as
+        // stable-asset fees, the asset V1 paid LP rewards in
+        if v1.lp_fees_accumulated > 0 {
+            portfolio.add_lp_fees(portfolio.stable_asset(), v1.lp_fees_accumulated);
+        }
+
+        for (user, position) in v1.lp_positions.iter() {
+            // The deposit ratio stands in for the entry price V1 didn't record
+            let entry_price = if position.xlm_deposited > 0 {
+                (position.usdc_deposited.max(0) as u128).saturating_mul(crate::trading::RATE_PRECISION)
+                    / (position.xlm_deposited as u128)
+            } else {
+                0
+            };
+            portfolio.set_lp_position(user, LPPosition {
+                lp_address: position.lp_address,
+                xlm_deposited: position.xlm_deposited,
+                usdc_deposited: position.usdc_deposited,
+                lp_tokens_minted: position.lp_tokens_minted,
+                entry_price,
+            });
+        }
+
+        for ((_, asset), balance) in portfolio.balances.clone().iter() {
+            portfolio.adjust_total_supply(asset, balance);
+        }
+        portfolio.adjust_total_supply(Asset::XLM, portfolio.xlm_in_pool);
+        portfolio.adjust_total_supply(portfolio.stable_asset(), portfolio.usdc_in_pool);
+
+        portfolio
+    }
+
     /// Transfer a user's balance from one asset to another.
     /// Fails if amount <= 0 or if the user has insufficient funds in the source asset.
     pub fn debit(&mut self, env: &Env, token: Asset, user: Address, amount: i128) {
@@ -182,23 +505,12 @@ impl Portfolio {
         self.metrics.balances_updated = self.metrics.balances_updated.saturating_add(1);
     }
 
-    /// Mint tokens (XLM or a custom token) to a user's balance.
+    /// Mint tokens (XLM or a custom token) to a user's balance, adding to the asset's supply.
     pub fn mint(&mut self, env: &Env, token: Asset, to: Address, amount: i128) {
         assert!(amount >= 0, "Amount must be non-negative");
 
-    let key = (to.clone(), token.clone());
-    let current = self.balances.get(key.clone()).unwrap_or(0);
-    let new_balance = current + amount;
-
-    self.balances.set(key, new_balance);
-
-        // Update PnL placeholder
-    let current_pnl = self.pnl.get(to.clone()).unwrap_or(0);
-    let new_pnl = current_pnl + amount;
-    self.pnl.set(to.clone(), new_pnl);
-
-        // Update top traders leaderboard
-        self.update_top_traders(env, to.clone());
+        self.apply_mint(env, token.clone(), to.clone(), amount);
+        self.adjust_total_supply(token.clone(), amount);
 
         // Metrics: one balance updated
         self.metrics.balances_updated = self.metrics.balances_updated.saturating_add(1);
@@ -214,17 +526,67 @@ impl Portfolio {
         }
     }
 
+    /// Mint `token` to every (address, amount) entry. All amounts are validated before
+    /// any balance changes; metrics are updated once for the whole batch.
+    pub fn batch_mint(&mut self, env: &Env, token: Asset, entries: &Vec<(Address, i128)>) {
+        for (_, amount) in entries.iter() {
+            assert!(amount >= 0, "Amount must be non-negative");
+        }
+
+        for (to, amount) in entries.iter() {
+            self.apply_mint(env, token.clone(), to, amount);
+            self.adjust_total_supply(token.clone(), amount);
+        }
+
+        self.metrics.balances_updated = self.metrics.balances_updated.saturating_add(entries.len());
+    }
+
+    /// Burn tokens from a user's balance, removing them from the asset's supply
+    pub fn burn(&mut self, env: &Env, token: Asset, from: Address, amount: i128) {
+        self.debit(env, token.clone(), from, amount);
+        self.adjust_total_supply(token, -amount);
+    }
+
+    /// Pay out tokens already in circulation (pool reserves, accrued fees) to a user.
+    /// Credits like a mint for balance, PnL and leaderboard, but leaves supply unchanged.
+    pub fn payout(&mut self, env: &Env, token: Asset, to: Address, amount: i128) {
+        assert!(amount >= 0, "Amount must be non-negative");
+        self.apply_mint(env, token, to, amount);
+        self.metrics.balances_updated = self.metrics.balances_updated.saturating_add(1);
+    }
+
+    /// Credit a minted amount: balance, PnL placeholder and leaderboard
+    fn apply_mint(&mut self, env: &Env, token: Asset, to: Address, amount: i128) {
+    self.mark_first_activity(env);
+    let key = (to.clone(), token.clone());
+    let current = self.balances.get(key.clone()).unwrap_or(0);
+    let new_balance = current + amount;
+
+    self.balances.set(key, new_balance);
+
+        // Update PnL placeholder
+    let current_pnl = self.pnl.get(to.clone()).unwrap_or(0);
+    let new_pnl = current_pnl + amount;
+    self.pnl.set(to.clone(), new_pnl);
+
+        // Update top traders leaderboard
+        self.update_top_traders(env, to);
+    }
+
     /// Record a swap execution (increase trade count).
-    /// Automatically awards "First Trade" badge if this is the user's first trade.
+    /// Automatically awards "First Trade" badge if this is the user's first trade,
+    /// unless badge checks are deferred.
     pub fn record_trade(&mut self, env: &Env, user: Address) {
     let count = self.trades.get(user.clone()).unwrap_or(0);
     self.trades.set(user.clone(), count + 1);
 
         // Metrics: successful trade executed
         self.metrics.trades_executed = self.metrics.trades_executed.saturating_add(1);
+        self.record_trade_checkpoint(env);
+        self.mark_daily_active(env, user.clone());
 
         // Award "First Trade" badge if this is the first trade
-        if count == 0 {
+        if count == 0 && !self.defer_badge_checks {
             self.award_badge(env, user, Badge::FirstTrade);
         }
     }
@@ -247,28 +609,31 @@ impl Portfolio {
         }
 
         // Award the badge
-    self.badges.set(key, true);
+    self.badges.set(key.clone(), true);
+        self.badge_award_times.set(key.clone(), env.ledger().timestamp());
+        self.record_activity(env, key.0, ActivityEvent::BadgeEarned(env.ledger().timestamp(), key.1));
         true
     }
 
+    /// Number of badges a user has earned, counted from the badge map without building the badge list
+    pub fn get_user_badge_count(&self, user: Address) -> u32 {
+        ALL_BADGES
+            .iter()
+            .filter(|badge| self.badges.get((user.clone(), (*badge).clone())).unwrap_or(false))
+            .count() as u32
+    }
+
+    /// Ledger timestamp at which a user earned a badge, if they have it.
+    pub fn get_badge_award_time(&self, user: Address, badge: Badge) -> Option<u64> {
+        self.badge_award_times.get((user, badge))
+    }
+
     /// Check if a user has earned a specific badge.
     pub fn has_badge(&self, env: &Env, user: Address, badge: Badge) -> bool {
         let key = (user, badge);
     self.badges.get(key).unwrap_or(false)
     }
 
-    /// Get all badges earned by a user.
-    pub fn get_user_badges(&self, env: &Env, user: Address) -> Vec<Badge> {
-    let mut badges = Vec::new(env);
-
-        // Check for FirstTrade badge
-        if self.has_badge(env, user.clone(), Badge::FirstTrade) {
-            badges.push_back(Badge::FirstTrade);
-        }
-
-        badges
-    }
-
     /// Get balance of a token for a given user.
     /// Returns 0 if no balance exists for the requested token/address.
     pub fn balance_of(&self, env: &Env, token: Asset, user: Address) -> i128 {
@@ -280,8 +645,44 @@ impl Portfolio {
     /// Returns (trade_count, pnl)
     pub fn get_portfolio(&self, env: &Env, user: Address) -> (u32, i128) {
         let trades = self.trades.get(user.clone()).unwrap_or(0);
-        let pnl = self.pnl.get(user).unwrap_or(0);
-        (trades, pnl)
+        (trades, self.reported_pnl(&user))
+    }
+
+    /// The user's PnL under the configured `PnlMode`
+    fn reported_pnl(&self, user: &Address) -> i128 {
+        match self.pnl_mode {
+            PnlMode::BalanceDelta => self.pnl.get(user.clone()).unwrap_or(0),
+            PnlMode::RealizedOnly => self.realized_pnl.get(user.clone()).unwrap_or(0),
+        }
+    }
+
+    pub fn get_pnl_mode(&self) -> PnlMode {
+        self.pnl_mode
+    }
+
+    /// Switch the reported PnL and rebuild the leaderboard under the new mode
+    pub fn set_pnl_mode(&mut self, env: &Env, mode: PnlMode) {
+        if self.pnl_mode == mode {
+            return;
+        }
+        self.pnl_mode = mode;
+
+        self.top_traders = Vec::new(env);
+        for user in self.pnl.keys().iter() {
+            self.update_top_traders(env, user);
+        }
+        for user in self.realized_pnl.keys().iter() {
+            self.update_top_traders(env, user);
+        }
+    }
+
+    /// Replace the leaderboard with the candidates ranked by their current PnL.
+    /// Traders left out of `users` drop off the board.
+    pub fn rebuild_leaderboard_from(&mut self, env: &Env, users: Vec<Address>) {
+        self.top_traders = Vec::new(env);
+        for user in users.iter() {
+            self.update_top_traders(env, user);
+        }
     }
 
     /// Read aggregate metrics
@@ -289,11 +690,307 @@ impl Portfolio {
         self.metrics.clone()
     }
 
+    /// Summarize swap count, volume, fees and failures
+    pub fn get_swap_statistics(&self) -> SwapStats {
+        let total_swaps = self.metrics.trades_executed;
+        let average_swap_size = if total_swaps == 0 {
+            0
+        } else {
+            self.lifetime_trading_volume / total_swaps as i128
+        };
+        SwapStats {
+            total_swaps,
+            total_volume: self.total_trading_volume,
+            total_fees: self.total_fees_collected,
+            average_swap_size,
+            failed_orders: self.metrics.failed_orders,
+        }
+    }
+
     /// Increment failed order counter
     pub fn inc_failed_order(&mut self) {
         self.metrics.failed_orders = self.metrics.failed_orders.saturating_add(1);
     }
 
+    /// Increment the failed order counter along with the counter for `reason`
+    pub fn inc_failed_order_for(&mut self, reason: OrderFailure) {
+        self.inc_failed_order();
+        let counter = match reason {
+            OrderFailure::Paused => &mut self.metrics.failed_paused,
+            OrderFailure::InvalidPair => &mut self.metrics.failed_invalid_pair,
+            OrderFailure::ZeroAmount => &mut self.metrics.failed_zero_amount,
+            OrderFailure::RateLimited => &mut self.metrics.failed_rate_limited,
+            // Counted in the total only
+            _ => return,
+        };
+        *counter = counter.saturating_add(1);
+    }
+
+    /// Count a rejected swap and add it to the user's failed-swap history,
+    /// which shares the transaction history cap (oldest evicted first)
+    pub fn record_failed_swap(
+        &mut self,
+        env: &Env,
+        user: Address,
+        from_token: Symbol,
+        to_token: Symbol,
+        amount: i128,
+        reason: OrderFailure,
+    ) {
+        self.inc_failed_order_for(reason);
+
+        let mut history = self.failed_swaps.get(user.clone()).unwrap_or_else(|| Vec::new(env));
+        history.push_back(FailedSwap {
+            timestamp: env.ledger().timestamp(),
+            from_token,
+            to_token,
+            amount,
+            reason,
+        });
+        while history.len() > self.tx_history_cap {
+            history.pop_front();
+        }
+        self.failed_swaps.set(user, history);
+    }
+
+    /// Up to `limit` of the user's most recent failed swaps, oldest first
+    pub fn get_user_failed_swaps(&self, env: &Env, user: Address, limit: u32) -> Vec<FailedSwap> {
+        let history = self.failed_swaps.get(user).unwrap_or_else(|| Vec::new(env));
+        let len = history.len();
+        if limit >= len {
+            return history;
+        }
+        history.slice((len - limit)..len)
+    }
+
+    // ===== TRANSACTION HISTORY =====
+
+    /// Append a transaction to the user's history.
+    /// History is capped at `tx_history_cap`; once full, the oldest entry is evicted first.
+    pub fn record_transaction(
+        &mut self,
+        env: &Env,
+        user: Address,
+        from_token: Symbol,
+        to_token: Symbol,
+        from_amount: i128,
+        to_amount: i128,
+    ) {
+        self.push_transaction(env, user.clone(), from_token.clone(), to_token.clone(), from_amount, to_amount, None);
+
+        // Value both legs in the stable asset so an XLM leg isn't netted against a stable one
+        let gain = self.value_in_stable(&to_token, to_amount) - self.value_in_stable(&from_token, from_amount);
+        let realized = self.realized_pnl.get(user.clone()).unwrap_or(0);
+        self.realized_pnl.set(user.clone(), realized.saturating_add(gain));
+        if self.pnl_mode == PnlMode::RealizedOnly {
+            self.update_top_traders(env, user);
+        }
+    }
+
+    /// Record a swap whose output of `to_amount` was credited to `recipient` rather than
+    /// `user`. It goes in `user`'s history but not their realized PnL: the output left
+    /// their hands, so neither leg is a gain or loss of theirs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_routed_transaction(
+        &mut self,
+        env: &Env,
+        user: Address,
+        from_token: Symbol,
+        to_token: Symbol,
+        from_amount: i128,
+        to_amount: i128,
+        recipient: Address,
+    ) {
+        self.push_transaction(env, user, from_token, to_token, from_amount, to_amount, Some(recipient));
+    }
+
+    /// Append a transaction to `user`'s history, evicting the oldest past the cap
+    #[allow(clippy::too_many_arguments)]
+    fn push_transaction(
+        &mut self,
+        env: &Env,
+        user: Address,
+        from_token: Symbol,
+        to_token: Symbol,
+        from_amount: i128,
+        to_amount: i128,
+        recipient: Option<Address>,
+    ) {
+        let scale = 10i128.pow(self.get_rate_decimals(&from_token, &to_token));
+        let rate_achieved = if from_amount > 0 {
+            (to_amount.saturating_mul(scale) / from_amount) as u128
+        } else {
+            0
+        };
+        let tx = Transaction {
+            timestamp: env.ledger().timestamp(),
+            from_token: from_token.clone(),
+            to_token: to_token.clone(),
+            from_amount,
+            to_amount,
+            rate_achieved,
+            migration_time: None,
+            recipient,
+        };
+
+        let mut history = self.transactions.get(user.clone()).unwrap_or_else(|| Vec::new(env));
+        history.push_back(tx);
+        while history.len() > self.tx_history_cap {
+            history.pop_front();
+        }
+        self.transactions.set(user, history);
+        self.mark_first_activity(env);
+    }
+
+    /// Add a user to today's active set, starting a fresh set when the UTC day has rolled over
+    fn mark_daily_active(&mut self, env: &Env, user: Address) {
+        let today = env.ledger().timestamp() / SECONDS_PER_DAY;
+        if today != self.daily_active_day {
+            self.daily_active_day = today;
+            self.daily_active_users = Map::new(env);
+        }
+        self.daily_active_users.set(user, true);
+    }
+
+    /// Unique users who traded during the current UTC day
+    pub fn get_daily_active_users(&self, env: &Env) -> u32 {
+        if env.ledger().timestamp() / SECONDS_PER_DAY != self.daily_active_day {
+            return 0;
+        }
+        self.daily_active_users.len()
+    }
+
+    /// Record the first-ever activity time; later calls leave it unchanged
+    fn mark_first_activity(&mut self, env: &Env) {
+        if self.first_activity_time.is_none() {
+            self.first_activity_time = Some(env.ledger().timestamp());
+        }
+    }
+
+    pub fn get_first_activity_time(&self) -> Option<u64> {
+        self.first_activity_time
+    }
+
+    /// Keep `amount` as the largest trade if it beats the current record (ties keep the earlier trade)
+    pub fn record_largest_trade(&mut self, user: Address, amount: i128) {
+        let is_larger = match &self.largest_trade {
+            Some((_, largest)) => amount > *largest,
+            None => amount > 0,
+        };
+        if is_larger {
+            self.largest_trade = Some((user, amount));
+        }
+    }
+
+    pub fn get_largest_trade(&self) -> Option<(Address, i128)> {
+        self.largest_trade.clone()
+    }
+
+    /// Store the details of the user's latest swap, replacing the previous one
+    pub fn record_swap_details(&mut self, user: Address, details: SwapDetails) {
+        self.last_swaps.set(user, details);
+    }
+
+    pub fn get_last_swap_details(&self, user: Address) -> Option<SwapDetails> {
+        self.last_swaps.get(user)
+    }
+
+    /// Get up to `limit` of the user's most recent transactions, oldest first.
+    /// `limit` is clamped to `max_tx_return`.
+    pub fn get_user_transactions(&self, env: &Env, user: Address, limit: u32) -> Vec<Transaction> {
+        let limit = limit.min(self.max_tx_return);
+        let history = self.transactions.get(user).unwrap_or_else(|| Vec::new(env));
+        let len = history.len();
+        if limit >= len {
+            return history;
+        }
+        history.slice((len - limit)..len)
+    }
+
+    /// Number of the user's retained transactions within the last `window_seconds` (inclusive).
+    /// Bounded by the transaction history cap.
+    pub fn get_user_trades_in_window(&self, env: &Env, user: Address, window_seconds: u64) -> u32 {
+        let cutoff = env.ledger().timestamp().saturating_sub(window_seconds);
+        let history = self.transactions.get(user).unwrap_or_else(|| Vec::new(env));
+        // History is oldest first, so count back from the newest entry
+        let mut count = 0;
+        for i in (0..history.len()).rev() {
+            if history.get(i).unwrap().timestamp < cutoff {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Append a non-trade event to the user's activity log, capped like transaction history
+    pub fn record_activity(&mut self, env: &Env, user: Address, event: ActivityEvent) {
+        let mut log = self.activity_log.get(user.clone()).unwrap_or_else(|| Vec::new(env));
+        log.push_back(event);
+        while log.len() > self.tx_history_cap {
+            log.pop_front();
+        }
+        self.activity_log.set(user, log);
+    }
+
+    /// Merge the user's trades with their LP and badge activity into one feed,
+    /// ordered by timestamp (trades first on ties). Returns the most recent `limit`
+    /// entries, oldest first.
+    pub fn get_activity_timeline(&self, env: &Env, user: Address, limit: u32) -> Vec<ActivityEvent> {
+        let trades = self.transactions.get(user.clone()).unwrap_or_else(|| Vec::new(env));
+        let others = self.activity_log.get(user).unwrap_or_else(|| Vec::new(env));
+
+        let mut timeline = Vec::new(env);
+        let (mut i, mut j) = (0, 0);
+        while i < trades.len() || j < others.len() {
+            let take_trade = match (trades.get(i), others.get(j)) {
+                (Some(tx), Some(event)) => tx.timestamp <= event.timestamp(),
+                (Some(_), None) => true,
+                _ => false,
+            };
+            if take_trade {
+                timeline.push_back(ActivityEvent::Trade(trades.get(i).unwrap()));
+                i += 1;
+            } else {
+                timeline.push_back(others.get(j).unwrap());
+                j += 1;
+            }
+        }
+
+        let len = timeline.len();
+        if limit >= len {
+            return timeline;
+        }
+        timeline.slice((len - limit)..len)
+    }
+
+    /// Set how many decimals a pair's `rate_achieved` is recorded with (capped at `MAX_RATE_DECIMALS`)
+    pub fn set_rate_decimals(&mut self, a: &Symbol, b: &Symbol, decimals: u32) {
+        self.rate_decimals.set(RateLimiter::canonical_pair(a, b), decimals.min(MAX_RATE_DECIMALS));
+    }
+
+    pub fn get_rate_decimals(&self, a: &Symbol, b: &Symbol) -> u32 {
+        self.rate_decimals.get(RateLimiter::canonical_pair(a, b)).unwrap_or(DEFAULT_RATE_DECIMALS)
+    }
+
+    /// Set the per-user transaction history cap (minimum 1)
+    pub fn set_tx_history_cap(&mut self, cap: u32) {
+        self.tx_history_cap = if cap == 0 { 1 } else { cap };
+    }
+
+    pub fn get_tx_history_cap(&self) -> u32 {
+        self.tx_history_cap
+    }
+
+    /// Set the most transactions one history query may return (minimum 1)
+    pub fn set_max_tx_return(&mut self, max: u32) {
+        self.max_tx_return = if max == 0 { 1 } else { max };
+    }
+
+    pub fn get_max_tx_return(&self) -> u32 {
+        self.max_tx_return
+    }
+
     // ===== BADGE & ACHIEVEMENT SYSTEM =====
 
     /// Update badge tracking when a trade occurs
@@ -342,15 +1039,14 @@ impl Portfolio {
     /// Check and automatically award all applicable badges to a user
     /// Call this after each trade or LP action
     pub fn check_and_award_badges(&mut self, env: &Env, user: Address) {
-        // FirstTrade: Complete 1 swap (already handled in record_trade)
-        // We keep it for consistency
-        
-        // Trader: Complete 10 swaps
-        let trades = self.trades.get(user.clone()).unwrap_or(0);
-        if trades >= 10 {
-            self.award_badge(env, user.clone(), Badge::Trader);
+        // FirstTrade: Complete 1 swap (handled in record_trade unless badge checks are deferred)
+        if self.trades.get(user.clone()).unwrap_or(0) >= 1 {
+            self.award_badge(env, user.clone(), Badge::FirstTrade);
         }
         
+        // Trader / VeteranTrader / MasterTrader: trade counts from the badge table
+        self.award_trade_count_badges(env, user.clone());
+        
         // WealthBuilder: Achieve 10x starting balance
         let current_balance = self.get_total_user_balance(env, user.clone());
         let initial_balance = self.initial_balances.get(user.clone()).unwrap_or(0);
@@ -378,12 +1074,133 @@ impl Portfolio {
         }
     }
 
+    /// Skip badge checks during trades and LP deposits; badges are then awarded by `check_and_award_badges` on demand
+    pub fn set_defer_badge_checks(&mut self, deferred: bool) {
+        self.defer_badge_checks = deferred;
+    }
+
+    pub fn badge_checks_deferred(&self) -> bool {
+        self.defer_badge_checks
+    }
+
+    /// Set how many of the first unique traders earn EarlyAdopter (0 disables it).
+    /// Traders already within the cutoff, in order of first trade, are awarded it now.
+    pub fn set_early_adopter_cutoff(&mut self, env: &Env, cutoff: u32) {
+        self.early_adopter_cutoff = cutoff;
+        let eligible = cutoff.min(self.active_users.len());
+        for i in 0..eligible {
+            if let Some(user) = self.active_users.get(i) {
+                self.award_badge(env, user, Badge::EarlyAdopter);
+            }
+        }
+    }
+
+    pub fn get_early_adopter_cutoff(&self) -> u32 {
+        self.early_adopter_cutoff
+    }
+
+    /// Trade count needed for a trade-count badge (admin override or default)
+    pub fn get_badge_threshold(&self, badge: Badge) -> u32 {
+        self.badge_thresholds
+            .get(badge.clone())
+            .unwrap_or_else(|| default_badge_threshold(&badge))
+    }
+
+    /// Override the trade count needed for a trade-count badge
+    pub fn set_badge_threshold(&mut self, badge: Badge, threshold: u32) {
+        assert!(TRADE_COUNT_BADGES.contains(&badge), "Not a trade-count badge");
+        assert!(threshold > 0, "Threshold must be positive");
+        self.badge_thresholds.set(badge, threshold);
+    }
+
+    /// Set the swap fee discount (bps) for holders of `badge`; 0 removes it
+    pub fn set_badge_fee_discount(&mut self, badge: Badge, bps: u32) {
+        assert!(bps <= 10000, "Discount must be <= 10000 bps");
+        if bps == 0 {
+            self.badge_fee_discounts.remove(badge);
+        } else {
+            self.badge_fee_discounts.set(badge, bps);
+        }
+    }
+
+    pub fn get_badge_fee_discount(&self, badge: Badge) -> u32 {
+        self.badge_fee_discounts.get(badge).unwrap_or(0)
+    }
+
+    /// Largest fee discount among the badges the user holds. Discounts don't stack.
+    pub fn best_badge_fee_discount(&self, env: &Env, user: Address) -> u32 {
+        let mut best = 0;
+        for (badge, bps) in self.badge_fee_discounts.iter() {
+            if bps > best && self.has_badge(env, user.clone(), badge) {
+                best = bps;
+            }
+        }
+        best
+    }
+
+    /// Swap fee for a user: their tier fee less their best badge discount, floored at zero
+    pub fn swap_fee_bps(&self, env: &Env, user: Address) -> u32 {
+        let tier_fee = self.get_user_tier(env, user.clone()).effective_fee_bps();
+        tier_fee.saturating_sub(self.best_badge_fee_discount(env, user))
+    }
+
+    /// Award every trade-count badge whose threshold the user has reached
+    fn award_trade_count_badges(&mut self, env: &Env, user: Address) {
+        let trades = self.trades.get(user.clone()).unwrap_or(0);
+        for badge in TRADE_COUNT_BADGES.iter() {
+            if trades >= self.get_badge_threshold(badge.clone()) {
+                self.award_badge(env, user.clone(), badge.clone());
+            }
+        }
+    }
+
+    /// Whether any trader qualifies for a trade-count badge they don't hold yet
+    pub fn has_pending_trade_badges(&self, env: &Env) -> bool {
+        for (user, trades) in self.trades.iter() {
+            for badge in TRADE_COUNT_BADGES.iter() {
+                if trades >= self.get_badge_threshold(badge.clone())
+                    && !self.has_badge(env, user.clone(), badge.clone())
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Award trade-count badges to existing traders, e.g. after new tiers are added
+    pub fn backfill_trade_badges(&mut self, env: &Env) {
+        for user in self.trades.keys().iter() {
+            self.award_trade_count_badges(env, user);
+        }
+    }
+
     /// Record an LP deposit for the user
     pub fn record_lp_deposit(&mut self, user: Address) {
         let count = self.lp_deposits_count.get(user.clone()).unwrap_or(0);
         self.lp_deposits_count.set(user, count.saturating_add(1));
     }
 
+    /// Append a deposit to the user's LP deposit history, capped like transaction history
+    pub fn record_lp_deposit_history(&mut self, env: &Env, user: Address, record: LPDepositRecord) {
+        let mut history = self.lp_deposit_history.get(user.clone()).unwrap_or_else(|| Vec::new(env));
+        history.push_back(record);
+        while history.len() > self.tx_history_cap {
+            history.pop_front();
+        }
+        self.lp_deposit_history.set(user, history);
+    }
+
+    /// Get up to `limit` of the user's most recent LP deposits, oldest first
+    pub fn get_lp_deposit_history(&self, env: &Env, user: Address, limit: u32) -> Vec<LPDepositRecord> {
+        let history = self.lp_deposit_history.get(user).unwrap_or_else(|| Vec::new(env));
+        let len = history.len();
+        if limit >= len {
+            return history;
+        }
+        history.slice((len - limit)..len)
+    }
+
     /// Record initial balance for WealthBuilder tracking
     pub fn record_initial_balance(&mut self, user: Address, amount: i128) {
         // Only set if not already recorded
@@ -407,8 +1224,10 @@ impl Portfolio {
         let trades = self.trades.get(user.clone()).unwrap_or(0);
         progress.push_back((Badge::FirstTrade, trades, 1));
         
-        // Trader: 10+ trades
-        progress.push_back((Badge::Trader, trades, 10));
+        // Trader / VeteranTrader / MasterTrader: trade counts from the badge table
+        for badge in TRADE_COUNT_BADGES.iter() {
+            progress.push_back((badge.clone(), trades, self.get_badge_threshold(badge.clone())));
+        }
         
         // WealthBuilder: 10x starting balance
         let current_balance = self.get_total_user_balance(env, user.clone());
@@ -435,21 +1254,11 @@ impl Portfolio {
         progress
     }
 
-    /// Update get_user_badges to include all earned badges
+    /// Get all badges earned by a user, in enum declaration order whatever order they were awarded in
     pub fn get_user_badges(&self, env: &Env, user: Address) -> Vec<Badge> {
-    let mut badges = Vec::new(env);
-
-        // Check all badge types
-        let badge_types = [
-            Badge::FirstTrade,
-            Badge::Trader,
-            Badge::WealthBuilder,
-            Badge::LiquidityProvider,
-            Badge::Diversifier,
-            Badge::Consistency,
-        ];
-        
-        for badge in badge_types.iter() {
+        let mut badges = Vec::new(env);
+
+        for badge in ALL_BADGES.iter() {
             if self.has_badge(env, user.clone(), badge.clone()) {
                 badges.push_back(badge.clone());
             }
@@ -482,6 +1291,47 @@ impl Portfolio {
         self.total_trading_volume
     }
 
+    /// Reset total trading volume to zero and start a new volume epoch
+    /// Returns the volume accumulated during the epoch that just ended
+    pub fn archive_and_reset_volume(&mut self) -> i128 {
+        let archived = self.total_trading_volume;
+        self.total_trading_volume = 0;
+        self.volume_epoch = self.volume_epoch.saturating_add(1);
+        archived
+    }
+
+    /// Get how many times the total trading volume has been archived and reset
+    pub fn get_volume_epoch(&self) -> u32 {
+        self.volume_epoch
+    }
+
+    /// Set the capped volume beyond which swaps are refused (0 removes the cap)
+    pub fn set_global_volume_cap(&mut self, cap: i128) {
+        assert!(cap >= 0, "Volume cap must be non-negative");
+        self.global_volume_cap = cap;
+    }
+
+    pub fn get_global_volume_cap(&self) -> i128 {
+        self.global_volume_cap
+    }
+
+    /// Whether a swap of gross input `amount` would take capped volume past the global cap
+    pub fn exceeds_volume_cap(&self, amount: i128) -> bool {
+        self.global_volume_cap > 0
+            && self.capped_volume.saturating_add(amount) > self.global_volume_cap
+    }
+
+    /// Count a swap's gross input toward the global volume cap
+    pub fn record_capped_volume(&mut self, amount: i128) {
+        self.capped_volume = self.capped_volume.saturating_add(amount);
+    }
+
+    /// Gross swap input counted against the global volume cap. Unlike the total trading
+    /// volume it is not reset by `archive_and_reset_volume`.
+    pub fn get_capped_volume(&self) -> i128 {
+        self.capped_volume
+    }
+
     /// Get the count of active users (users with recorded trades)
     /// Returns u32: count of users in active_users list
     /// Time complexity: O(1)
@@ -518,7 +1368,7 @@ impl Portfolio {
 
     /// Helper: Update aggregate stats when a trade is recorded
     /// Called lazily during trade operations
-    fn update_stats_on_trade(&mut self, env: &Env, user: Address, swap_amount: i128) {
+    pub fn update_stats_on_trade(&mut self, env: &Env, user: Address, swap_amount: i128) {
         // Check if user is new (not in trades map)
         let trade_count = self.trades.get(user.clone()).unwrap_or(0);
         if trade_count == 0 {
@@ -537,16 +1387,22 @@ impl Portfolio {
             if !is_active {
                 self.active_users.push_back(user.clone());
             }
+
+            // Arrival order can't be reconstructed later, so this is awarded even when badge checks are deferred
+            if self.total_users <= self.early_adopter_cutoff {
+                self.award_badge(env, user.clone(), Badge::EarlyAdopter);
+            }
         }
         
         // Update total trading volume
         self.total_trading_volume = self.total_trading_volume.saturating_add(swap_amount);
+        self.lifetime_trading_volume = self.lifetime_trading_volume.saturating_add(swap_amount);
     }
 
     /// Helper: Update top traders leaderboard after PnL changes
     /// Maintains top 100 traders sorted by PnL descending
     fn update_top_traders(&mut self, env: &Env, user: Address) {
-        let user_pnl = self.pnl.get(user.clone()).unwrap_or(0);
+        let user_pnl = self.reported_pnl(&user);
         
         // Check if user is already in top_traders
         let mut found_index = None;
@@ -578,13 +1434,27 @@ impl Portfolio {
         self.sort_top_traders();
     }
 
-    /// Helper: Sort top_traders by PnL in descending order
+    /// Whether `a` ranks below `b` on the leaderboard: lower PnL first, then on equal PnL
+    /// fewer trades, then the higher address, so ties always resolve the same way
+    fn ranks_below(&self, a: &(Address, i128), b: &(Address, i128)) -> bool {
+        if a.1 != b.1 {
+            return a.1 < b.1;
+        }
+        let trades_a = self.trades.get(a.0.clone()).unwrap_or(0);
+        let trades_b = self.trades.get(b.0.clone()).unwrap_or(0);
+        if trades_a != trades_b {
+            return trades_a < trades_b;
+        }
+        a.0 > b.0
+    }
+
+    /// Helper: Sort top_traders by PnL in descending order, ties broken by `ranks_below`
     fn sort_top_traders(&mut self) {
         let len = self.top_traders.len();
         for i in 0..len {
             for j in 0..(len - 1 - i) {
-                if let (Some((_, pnl1)), Some((_, pnl2))) = (self.top_traders.get(j), self.top_traders.get(j + 1)) {
-                    if pnl1 < pnl2 {
+                if let (Some(first), Some(second)) = (self.top_traders.get(j), self.top_traders.get(j + 1)) {
+                    if self.ranks_below(&first, &second) {
                         // Swap
                         let temp1 = self.top_traders.get(j).unwrap();
                         let temp2 = self.top_traders.get(j + 1).unwrap();
@@ -596,22 +1466,256 @@ impl Portfolio {
         }
     }
 
+    /// Get the issued supply of an asset: minted minus burned. Tokens moving between balances,
+    /// pool reserves and accrued fees stay in supply.
+    pub fn get_total_supply(&self, asset: Asset) -> i128 {
+        self.total_supply.get(asset).unwrap_or(0)
+    }
+
+    /// Adjust an asset's issued supply by `delta` (positive on mint, negative on burn)
+    fn adjust_total_supply(&mut self, asset: Asset, delta: i128) {
+        let current = self.get_total_supply(asset.clone());
+        self.total_supply.set(asset, current.saturating_add(delta));
+    }
+
+    /// Accumulate swap input volume by direction (XLM->USDCSIM vs USDCSIM->XLM),
+    /// both pool-wide and for the swapping user
+    pub fn record_directional_volume(&mut self, user: Address, from_asset: Asset, amount: i128) {
+        let (mut sold_xlm, mut sold_usdc) = self.user_directional_volume.get(user.clone()).unwrap_or((0, 0));
+        if from_asset == Asset::XLM {
+            self.xlm_to_usdc_volume = self.xlm_to_usdc_volume.saturating_add(amount);
+            sold_xlm = sold_xlm.saturating_add(amount);
+        } else {
+            self.usdc_to_xlm_volume = self.usdc_to_xlm_volume.saturating_add(amount);
+            sold_usdc = sold_usdc.saturating_add(amount);
+        }
+        self.user_directional_volume.set(user, (sold_xlm, sold_usdc));
+    }
+
+    /// Returns (xlm_to_usdc_volume, usdc_to_xlm_volume)
+    pub fn get_directional_volume(&self) -> (i128, i128) {
+        (self.xlm_to_usdc_volume, self.usdc_to_xlm_volume)
+    }
+
+    /// Returns the user's (xlm_to_usdc_volume, usdc_to_xlm_volume)
+    pub fn get_user_direction_ratio(&self, user: Address) -> (i128, i128) {
+        self.user_directional_volume.get(user).unwrap_or((0, 0))
+    }
+
     /// Helper: Add liquidity to pool
     pub fn add_pool_liquidity(&mut self, xlm_amount: i128, usdc_amount: i128) {
         self.xlm_in_pool = self.xlm_in_pool.saturating_add(xlm_amount);
         self.usdc_in_pool = self.usdc_in_pool.saturating_add(usdc_amount);
     }
 
+    /// Charge the swap fee on `amount` at `fee_bps` (rounded up): debit it from the user's `from`
+    /// balance and book it in the fee totals, pair revenue and the user's fees paid.
+    /// Returns the fee charged; the rest of `amount` is what gets swapped.
+    pub fn apply_swap_fee(
+        &mut self,
+        env: &Env,
+        user: Address,
+        from: &Symbol,
+        to: &Symbol,
+        amount: i128,
+        fee_bps: u32,
+    ) -> i128 {
+        let fee_amount = crate::trading::fee_round_up(amount, fee_bps);
+        if fee_amount > 0 {
+            let fee_asset = if *from == symbol_short!("XLM") { Asset::XLM } else { Asset::Custom(from.clone()) };
+            self.debit(env, fee_asset.clone(), user.clone(), fee_amount);
+            self.collect_fee(fee_amount);
+            self.record_protocol_fee(fee_asset, fee_amount);
+            self.record_fee_checkpoint(env);
+            self.record_pair_fee(from, to, fee_amount);
+            self.record_fee_paid(user, fee_amount);
+        }
+        fee_amount
+    }
+
     /// Helper: Collect fees
     pub fn collect_fee(&mut self, fee_amount: i128) {
         self.total_fees_collected = self.total_fees_collected.saturating_add(fee_amount);
     }
 
+    /// Hold a collected swap fee for the protocol, in the asset it was paid in
+    pub fn record_protocol_fee(&mut self, asset: Asset, fee_amount: i128) {
+        let held = self.protocol_fees.get(asset.clone()).unwrap_or(0);
+        self.protocol_fees.set(asset, held.saturating_add(fee_amount));
+    }
+
+    /// Get the swap fees held by the protocol in one asset
+    pub fn get_protocol_fees(&self, asset: Asset) -> i128 {
+        self.protocol_fees.get(asset).unwrap_or(0)
+    }
+
+    /// Attribute a swap fee to its trading pair; both directions share one entry
+    pub fn record_pair_fee(&mut self, from: &Symbol, to: &Symbol, fee_amount: i128) {
+        let pair = RateLimiter::canonical_pair(from, to);
+        let collected = self.pair_fees.get(pair.clone()).unwrap_or(0);
+        self.pair_fees.set(pair, collected.saturating_add(fee_amount));
+    }
+
+    /// Swap fees collected on a pair, in either direction
+    pub fn get_fee_revenue_by_pair(&self, from: &Symbol, to: &Symbol) -> i128 {
+        self.pair_fees.get(RateLimiter::canonical_pair(from, to)).unwrap_or(0)
+    }
+
+    /// Add to a user's lifetime fees paid
+    pub fn record_fee_paid(&mut self, user: Address, fee_amount: i128) {
+        let paid = self.fees_paid.get(user.clone()).unwrap_or(0);
+        self.fees_paid.set(user, paid.saturating_add(fee_amount));
+    }
+
+    /// Get a user's lifetime fees paid
+    pub fn get_user_fees_paid(&self, user: Address) -> i128 {
+        self.fees_paid.get(user).unwrap_or(0)
+    }
+
+    /// Snapshot cumulative fees at the current timestamp.
+    /// One checkpoint per timestamp; the oldest is evicted once `MAX_FEE_CHECKPOINTS` is reached.
+    pub fn record_fee_checkpoint(&mut self, env: &Env) {
+        let now = env.ledger().timestamp();
+        let len = self.fee_checkpoints.len();
+        if len > 0 && self.fee_checkpoints.get(len - 1).unwrap().0 == now {
+            self.fee_checkpoints.set(len - 1, (now, self.total_fees_collected));
+            return;
+        }
+        self.fee_checkpoints.push_back((now, self.total_fees_collected));
+        while self.fee_checkpoints.len() > MAX_FEE_CHECKPOINTS {
+            self.fee_checkpoints.pop_front();
+        }
+    }
+
+    /// Fees collected in the last `window_seconds`, by differencing against the latest
+    /// checkpoint at or before the window start. Windows reaching past the retained
+    /// history are measured from the oldest checkpoint once the buffer has filled.
+    pub fn get_fee_revenue_in_window(&self, env: &Env, window_seconds: u64) -> i128 {
+        let cutoff = env.ledger().timestamp().saturating_sub(window_seconds);
+
+        let mut baseline: Option<i128> = None;
+        for (ts, cumulative) in self.fee_checkpoints.iter() {
+            if ts > cutoff {
+                break;
+            }
+            baseline = Some(cumulative);
+        }
+
+        let baseline = match baseline {
+            Some(cumulative) => cumulative,
+            None if self.fee_checkpoints.len() >= MAX_FEE_CHECKPOINTS => {
+                self.fee_checkpoints.get(0).map(|(_, cumulative)| cumulative).unwrap_or(0)
+            }
+            None => 0,
+        };
+        self.total_fees_collected.saturating_sub(baseline)
+    }
+
+    /// Accrue swap volume into the current utilization window and refresh its
+    /// utilization (volume relative to current pool size, in bps).
+    /// The oldest window is evicted once `MAX_UTILIZATION_WINDOWS` is reached.
+    pub fn record_utilization(&mut self, env: &Env, volume: i128) {
+        let now = env.ledger().timestamp();
+        let window_start = now - now % UTILIZATION_WINDOW_SECONDS;
+
+        let len = self.utilization_history.len();
+        let same_window = len > 0 && self.utilization_history.get(len - 1).unwrap().0 == window_start;
+        self.utilization_window_volume = if same_window {
+            self.utilization_window_volume.saturating_add(volume)
+        } else {
+            volume
+        };
+
+        let pool_size = self.xlm_in_pool.saturating_add(self.usdc_in_pool);
+        let bps = if pool_size > 0 {
+            (self.utilization_window_volume.saturating_mul(10000) / pool_size).min(u32::MAX as i128) as u32
+        } else {
+            0
+        };
+
+        if same_window {
+            self.utilization_history.set(len - 1, (window_start, bps));
+            return;
+        }
+        self.utilization_history.push_back((window_start, bps));
+        while self.utilization_history.len() > MAX_UTILIZATION_WINDOWS {
+            self.utilization_history.pop_front();
+        }
+    }
+
+    /// Most recent `limit` utilization windows as (window start, utilization bps), oldest first
+    pub fn get_utilization_history(&self, env: &Env, limit: u32) -> Vec<(u64, u32)> {
+        let len = self.utilization_history.len();
+        let start = len.saturating_sub(limit);
+        let mut out = Vec::new(env);
+        for i in start..len {
+            out.push_back(self.utilization_history.get(i).unwrap());
+        }
+        out
+    }
+
+    /// Snapshot the cumulative trade count at the current timestamp.
+    /// One checkpoint per timestamp; the oldest is evicted once `MAX_TRADE_CHECKPOINTS` is reached.
+    fn record_trade_checkpoint(&mut self, env: &Env) {
+        let now = env.ledger().timestamp();
+        let total = self.metrics.trades_executed;
+        let len = self.trade_checkpoints.len();
+        if len > 0 && self.trade_checkpoints.get(len - 1).unwrap().0 == now {
+            self.trade_checkpoints.set(len - 1, (now, total));
+            return;
+        }
+        self.trade_checkpoints.push_back((now, total));
+        while self.trade_checkpoints.len() > MAX_TRADE_CHECKPOINTS {
+            self.trade_checkpoints.pop_front();
+        }
+    }
+
+    /// Platform-wide trades in the last `window_seconds`, by differencing against the latest
+    /// checkpoint at or before the window start. Windows reaching past the retained
+    /// history are measured from the oldest checkpoint once the buffer has filled.
+    pub fn get_global_trades_in_window(&self, env: &Env, window_seconds: u64) -> u32 {
+        let cutoff = env.ledger().timestamp().saturating_sub(window_seconds);
+
+        let mut baseline: Option<u32> = None;
+        for (ts, cumulative) in self.trade_checkpoints.iter() {
+            if ts > cutoff {
+                break;
+            }
+            baseline = Some(cumulative);
+        }
+
+        let baseline = match baseline {
+            Some(cumulative) => cumulative,
+            None if self.trade_checkpoints.len() >= MAX_TRADE_CHECKPOINTS => {
+                self.trade_checkpoints.get(0).map(|(_, cumulative)| cumulative).unwrap_or(0)
+            }
+            None => 0,
+        };
+        self.metrics.trades_executed.saturating_sub(baseline)
+    }
+
+    /// Symbol of the pool's stable asset
+    pub fn stable_symbol(&self) -> Symbol {
+        self.stable_symbol.clone()
+    }
+
+    /// The pool's stable asset
+    pub fn stable_asset(&self) -> Asset {
+        Asset::Custom(self.stable_symbol.clone())
+    }
+
+    /// Change the stable asset symbol; only allowed before the pool holds any liquidity
+    pub fn set_stable_symbol(&mut self, symbol: Symbol) {
+        assert!(symbol != symbol_short!("XLM"), "Stable asset must differ from XLM");
+        assert!(self.xlm_in_pool == 0 && self.usdc_in_pool == 0, "Pool already has liquidity");
+        self.stable_symbol = symbol;
+    }
+
     pub fn set_liquidity(&mut self, asset: Asset, amount: i128) {
         match asset {
             Asset::XLM => self.xlm_in_pool = amount,
             Asset::Custom(sym) => {
-                if sym == symbol_short!("USDCSIM") {
+                if sym == self.stable_symbol {
                     self.usdc_in_pool = amount;
                 }
             }
@@ -622,7 +1726,7 @@ impl Portfolio {
         match asset {
             Asset::XLM => self.xlm_in_pool,
             Asset::Custom(sym) => {
-                if sym == symbol_short!("USDCSIM") {
+                if sym == self.stable_symbol {
                     self.usdc_in_pool
                 } else {
                     0
@@ -631,6 +1735,53 @@ impl Portfolio {
         }
     }
 
+    /// Value an XLM amount in USDCSIM at the current pool rate.
+    /// Falls back to 1:1 when the pool has no liquidity, matching the swap fallback.
+    pub fn xlm_value_in_usdc(&self, amount: i128) -> i128 {
+        if self.xlm_in_pool <= 0 || self.usdc_in_pool <= 0 {
+            return amount;
+        }
+        ((amount as u128).saturating_mul(self.usdc_in_pool as u128) / (self.xlm_in_pool as u128)) as i128
+    }
+
+    /// Value `amount` of `token` in the stable asset; XLM at the pool rate, anything else as-is
+    fn value_in_stable(&self, token: &Symbol, amount: i128) -> i128 {
+        if *token == symbol_short!("XLM") {
+            self.xlm_value_in_usdc(amount)
+        } else {
+            amount
+        }
+    }
+
+    /// Total value locked in the pool in USDCSIM, with XLM valued at the pool rate. 0 for an empty pool.
+    pub fn get_tvl_usdc(&self) -> i128 {
+        if self.xlm_in_pool <= 0 && self.usdc_in_pool <= 0 {
+            return 0;
+        }
+        self.xlm_value_in_usdc(self.xlm_in_pool).saturating_add(self.usdc_in_pool)
+    }
+
+    /// Value of a user's LP position in USDCSIM: their LP-token share of both reserves.
+    pub fn lp_position_value_in_usdc(&self, user: Address) -> i128 {
+        let pos = match self.lp_positions.get(user) {
+            Some(pos) => pos,
+            None => return 0,
+        };
+        self.lp_tokens_value_in_usdc(pos.lp_tokens_minted)
+    }
+
+    /// Value in USDCSIM of `lp_tokens`' share of both reserves
+    fn lp_tokens_value_in_usdc(&self, lp_tokens: i128) -> i128 {
+        if lp_tokens <= 0 || self.total_lp_tokens <= 0 {
+            return 0;
+        }
+        let share_xlm = ((lp_tokens as u128).saturating_mul(self.xlm_in_pool as u128)
+            / (self.total_lp_tokens as u128)) as i128;
+        let share_usdc = ((lp_tokens as u128).saturating_mul(self.usdc_in_pool as u128)
+            / (self.total_lp_tokens as u128)) as i128;
+        self.xlm_value_in_usdc(share_xlm).saturating_add(share_usdc)
+    }
+
     // ===== LP POSITION MANAGEMENT =====
 
     /// Get LP position for a user
@@ -638,16 +1789,149 @@ impl Portfolio {
         self.lp_positions.get(user)
     }
 
+    /// Rebuild a user's LP position from their LP token balance and the current pool ratio,
+    /// discarding any stale deposit figures. Returns the cleaned position, if one exists.
+    pub fn consolidate_lp_position(&mut self, user: Address) -> Option<LPPosition> {
+        let mut position = self.lp_positions.get(user.clone())?;
+        let was_active = position.lp_tokens_minted > 0;
+
+        position.lp_address = user.clone();
+        if position.lp_tokens_minted <= 0 || self.total_lp_tokens <= 0 {
+            position.lp_tokens_minted = 0;
+            position.xlm_deposited = 0;
+            position.usdc_deposited = 0;
+            position.entry_price = 0;
+        } else {
+            let tokens = position.lp_tokens_minted.min(self.total_lp_tokens);
+            position.lp_tokens_minted = tokens;
+            position.xlm_deposited = self.xlm_in_pool.saturating_mul(tokens) / self.total_lp_tokens;
+            position.usdc_deposited = self.usdc_in_pool.saturating_mul(tokens) / self.total_lp_tokens;
+        }
+
+        self.update_active_lp_count(was_active, position.lp_tokens_minted > 0);
+        self.lp_positions.set(user, position.clone());
+        Some(position)
+    }
+
     /// Set or update LP position for a user
     pub fn set_lp_position(&mut self, user: Address, position: LPPosition) {
+        let was_active = match self.lp_positions.get(user.clone()) {
+            Some(existing) => existing.lp_tokens_minted > 0,
+            None => {
+                self.lp_providers.push_back(user.clone());
+                false
+            }
+        };
+        self.update_active_lp_count(was_active, position.lp_tokens_minted > 0);
         self.lp_positions.set(user, position);
     }
 
+    fn update_active_lp_count(&mut self, was_active: bool, is_active: bool) {
+        if is_active && !was_active {
+            self.active_lp_count = self.active_lp_count.saturating_add(1);
+        } else if was_active && !is_active {
+            self.active_lp_count = self.active_lp_count.saturating_sub(1);
+        }
+    }
+
+    /// Number of providers currently holding LP tokens
+    pub fn get_lp_count(&self) -> u32 {
+        self.active_lp_count
+    }
+
     /// Get total LP tokens minted
     pub fn get_total_lp_tokens(&self) -> i128 {
         self.total_lp_tokens
     }
 
+    /// Move the protocol's held swap fees into the reserves at the current reserve ratio, so
+    /// the pool price doesn't move, minting protocol-owned LP tokens for them. Whatever
+    /// doesn't fit the ratio stays in the protocol fee balances for a later call. Lifetime fee
+    /// stats are left alone. Returns the LP tokens minted (0 if nothing was compounded).
+    pub fn compound_protocol_fees(&mut self) -> i128 {
+        let xlm_fees = self.get_protocol_fees(Asset::XLM).max(0);
+        let usdc_fees = self.get_protocol_fees(self.stable_asset()).max(0);
+        if self.xlm_in_pool <= 0 || self.usdc_in_pool <= 0 || self.total_lp_tokens <= 0 {
+            return 0;
+        }
+
+        // The largest (xlm, usdc) pair at the reserve ratio that both balances cover
+        let xlm_add = xlm_fees.min(usdc_fees.saturating_mul(self.xlm_in_pool) / self.usdc_in_pool);
+        let usdc_add = xlm_add.saturating_mul(self.usdc_in_pool) / self.xlm_in_pool;
+        let minted = core::cmp::min(
+            xlm_add.saturating_mul(self.total_lp_tokens) / self.xlm_in_pool,
+            usdc_add.saturating_mul(self.total_lp_tokens) / self.usdc_in_pool,
+        );
+        if minted <= 0 {
+            return 0;
+        }
+
+        let cost = self.xlm_value_in_usdc(xlm_add).saturating_add(usdc_add);
+        self.add_pool_liquidity(xlm_add, usdc_add);
+        self.protocol_fees.set(Asset::XLM, xlm_fees - xlm_add);
+        self.protocol_fees.set(self.stable_asset(), usdc_fees - usdc_add);
+        self.protocol_lp_tokens = self.protocol_lp_tokens.saturating_add(minted);
+        self.protocol_lp_cost = self.protocol_lp_cost.saturating_add(cost);
+        self.protocol_fees_compounded = self.protocol_fees_compounded.saturating_add(cost);
+        self.total_lp_tokens = self.total_lp_tokens.saturating_add(minted);
+        minted
+    }
+
+    /// Stable-asset value of all protocol fees compounded into the pool so far
+    pub fn get_protocol_fees_compounded(&self) -> i128 {
+        self.protocol_fees_compounded
+    }
+
+    /// Add protocol-owned liquidity to the pool, funded from `source`'s balances, minting LP
+    /// tokens as a regular deposit would (geometric mean for an empty pool, otherwise the
+    /// smaller proportional share). The deposit's value at the current pool rate is recorded
+    /// as the protocol's cost. Returns the LP tokens minted.
+    pub fn seed_protocol_liquidity(&mut self, env: &Env, source: Address, xlm_amount: i128, usdc_amount: i128) -> i128 {
+        assert!(xlm_amount > 0 && usdc_amount > 0, "Seed amounts must be positive");
+        self.debit(env, Asset::XLM, source.clone(), xlm_amount);
+        self.debit(env, self.stable_asset(), source, usdc_amount);
+
+        let minted = if self.total_lp_tokens <= 0 || self.xlm_in_pool <= 0 || self.usdc_in_pool <= 0 {
+            crate::trading::integer_sqrt((xlm_amount as u128).saturating_mul(usdc_amount as u128)) as i128
+        } else {
+            core::cmp::min(
+                xlm_amount.saturating_mul(self.total_lp_tokens) / self.xlm_in_pool,
+                usdc_amount.saturating_mul(self.total_lp_tokens) / self.usdc_in_pool,
+            )
+        };
+        let cost = self.xlm_value_in_usdc(xlm_amount).saturating_add(usdc_amount);
+
+        self.add_pool_liquidity(xlm_amount, usdc_amount);
+        self.protocol_lp_tokens = self.protocol_lp_tokens.saturating_add(minted);
+        self.protocol_lp_cost = self.protocol_lp_cost.saturating_add(cost);
+        self.total_lp_tokens = self.total_lp_tokens.saturating_add(minted);
+        minted
+    }
+
+    /// LP tokens owned by the protocol
+    pub fn get_protocol_lp_tokens(&self) -> i128 {
+        self.protocol_lp_tokens
+    }
+
+    /// Current value of the protocol's LP share minus what it put in, in USDCSIM
+    pub fn get_protocol_lp_pnl(&self) -> i128 {
+        self.lp_tokens_value_in_usdc(self.protocol_lp_tokens)
+            .saturating_sub(self.protocol_lp_cost)
+    }
+
+    /// Rebuild `total_lp_tokens` as the sum of every provider's LP tokens plus the protocol's,
+    /// repairing drift between the total and individual positions. Returns the new total.
+    pub fn recompute_total_lp_tokens(&mut self) -> i128 {
+        let mut total: i128 = self.protocol_lp_tokens.max(0);
+        for provider in self.lp_providers.iter() {
+            if let Some(pos) = self.lp_positions.get(provider) {
+                total = total.saturating_add(pos.lp_tokens_minted.max(0));
+            }
+        }
+        self.total_lp_tokens = total;
+        total
+    }
+
     /// Add to total LP tokens (when minting)
     pub fn add_total_lp_tokens(&mut self, amount: i128) {
         self.total_lp_tokens = self.total_lp_tokens.saturating_add(amount);
@@ -661,14 +1945,169 @@ impl Portfolio {
         }
     }
 
-    /// Add accumulated fees for LP distribution
-    pub fn add_lp_fees(&mut self, amount: i128) {
-        self.lp_fees_accumulated = self.lp_fees_accumulated.saturating_add(amount);
+    /// Add accumulated fees for LP distribution, in the asset they were paid in
+    pub fn add_lp_fees(&mut self, asset: Asset, amount: i128) {
+        let current = self.lp_fees_accumulated.get(asset.clone()).unwrap_or(0);
+        self.lp_fees_accumulated.set(asset, current.saturating_add(amount));
+    }
+
+    /// Get accumulated LP fees in one asset
+    pub fn get_lp_fees_accumulated(&self, asset: Asset) -> i128 {
+        self.lp_fees_accumulated.get(asset).unwrap_or(0)
+    }
+
+    /// Record the time of an LP deposit; a top-up restarts the holding clock
+    pub fn record_lp_deposit_time(&mut self, env: &Env, user: Address) {
+        self.lp_deposit_times.set(user, env.ledger().timestamp());
+    }
+
+    /// Set the minimum time a position must be held before it earns fees
+    pub fn set_lp_min_hold_seconds(&mut self, seconds: u64) {
+        self.lp_min_hold_seconds = seconds;
+    }
+
+    pub fn get_lp_min_hold_seconds(&self) -> u64 {
+        self.lp_min_hold_seconds
+    }
+
+    /// Set the fee (bps) withheld from LP withdrawals
+    pub fn set_lp_exit_fee_bps(&mut self, bps: u32) {
+        assert!(bps <= 10000, "Exit fee must be <= 10000 bps");
+        self.lp_exit_fee_bps = bps;
+    }
+
+    pub fn get_lp_exit_fee_bps(&self) -> u32 {
+        self.lp_exit_fee_bps
+    }
+
+    /// Set the smallest LP deposit accepted, valued in the stable asset
+    pub fn set_min_lp_deposit(&mut self, amount: i128) {
+        assert!(amount >= 0, "Minimum deposit must be non-negative");
+        self.min_lp_deposit = amount;
+    }
+
+    pub fn get_min_lp_deposit(&self) -> i128 {
+        self.min_lp_deposit
+    }
+
+    /// Set the most LP positions one user may hold; `None` removes the limit
+    pub fn set_max_positions_per_user(&mut self, max: Option<u32>) {
+        self.max_positions_per_user = max;
+    }
+
+    pub fn get_max_positions_per_user(&self) -> Option<u32> {
+        self.max_positions_per_user
+    }
+
+    /// Number of LP positions a user currently holds. With a single pool this is 0 or 1.
+    pub fn get_user_position_count(&self, user: Address) -> u32 {
+        match self.lp_positions.get(user) {
+            Some(position) if position.lp_tokens_minted > 0 => 1,
+            _ => 0,
+        }
+    }
+
+    /// Whether a deposit from `user` may go ahead: topping up an open position always may,
+    /// opening a new one only while the user is below `max_positions_per_user`
+    pub fn can_open_lp_position(&self, user: Address) -> bool {
+        // Only one pool exists, so any open position is the one being topped up
+        let open = self.get_user_position_count(user);
+        open > 0 || self.max_positions_per_user.map_or(true, |max| open < max)
+    }
+
+    /// Whether a user's LP position has been held long enough to earn fees
+    pub fn is_lp_fee_eligible(&self, env: &Env, user: Address) -> bool {
+        let deposited_at = self.lp_deposit_times.get(user).unwrap_or(0);
+        env.ledger().timestamp().saturating_sub(deposited_at) >= self.lp_min_hold_seconds
+    }
+
+    /// A position's pro-rata share of the LP fees accumulated in `asset`
+    fn lp_fee_share(&self, asset: Asset, position: &LPPosition) -> i128 {
+        let fees = self.get_lp_fees_accumulated(asset);
+        if fees <= 0 || self.total_lp_tokens <= 0 || position.lp_tokens_minted <= 0 {
+            return 0;
+        }
+        ((fees as u128).saturating_mul(position.lp_tokens_minted as u128)
+            / (self.total_lp_tokens as u128)) as i128
     }
 
-    /// Get accumulated LP fees
-    pub fn get_lp_fees_accumulated(&self) -> i128 {
-        self.lp_fees_accumulated
+    /// Get the LP fees a user would be paid if fees were settled now, summed over all
+    /// their positions, as (XLM, stable asset). Positions still inside the minimum
+    /// holding time contribute nothing.
+    pub fn get_total_pending_lp_rewards(&self, env: &Env, user: Address) -> (i128, i128) {
+        if !self.is_lp_fee_eligible(env, user.clone()) {
+            return (0, 0);
+        }
+        // Only the XLM/USDCSIM pool exists, so a user holds at most one position
+        match self.lp_positions.get(user) {
+            Some(position) => (
+                self.lp_fee_share(Asset::XLM, &position),
+                self.lp_fee_share(self.stable_asset(), &position),
+            ),
+            None => (0, 0),
+        }
+    }
+
+    /// Pay out all accumulated LP fees to current providers, pro rata by LP tokens.
+    /// Each asset's fees are paid out in that asset; the protocol's LP tokens earn their share
+    /// into the protocol fee balance, and rounding dust goes back to that asset's pool
+    /// reserve. Shares of positions held less than the minimum holding time
+    /// stay accumulated for a later settlement. Returns the amounts distributed as
+    /// (XLM, stable asset).
+    pub fn settle_all_lp_fees(&mut self, env: &Env) -> (i128, i128) {
+        (
+            self.settle_lp_fees_in(env, Asset::XLM),
+            self.settle_lp_fees_in(env, self.stable_asset()),
+        )
+    }
+
+    /// Settle the LP fees accumulated in one asset; see `settle_all_lp_fees`
+    fn settle_lp_fees_in(&mut self, env: &Env, asset: Asset) -> i128 {
+        let fees = self.get_lp_fees_accumulated(asset.clone());
+        if fees <= 0 || self.total_lp_tokens <= 0 {
+            return 0;
+        }
+
+        let mut distributed = 0i128;
+        let mut withheld = 0i128;
+        let providers = self.lp_providers.clone();
+        for provider in providers.iter() {
+            if let Some(pos) = self.lp_positions.get(provider.clone()) {
+                if pos.lp_tokens_minted <= 0 {
+                    continue;
+                }
+                let share = self.lp_fee_share(asset.clone(), &pos);
+                if !self.is_lp_fee_eligible(env, provider.clone()) {
+                    withheld = withheld.saturating_add(share);
+                    continue;
+                }
+                if share > 0 {
+                    self.payout(env, asset.clone(), provider.clone(), share);
+                    distributed = distributed.saturating_add(share);
+                }
+            }
+        }
+
+        // The protocol-owned share is held with the protocol's swap fees, to be withdrawn or compounded
+        let protocol_share = if self.protocol_lp_tokens > 0 {
+            ((fees as u128).saturating_mul(self.protocol_lp_tokens as u128) / (self.total_lp_tokens as u128)) as i128
+        } else {
+            0
+        };
+        if protocol_share > 0 {
+            self.record_protocol_fee(asset.clone(), protocol_share);
+        }
+
+        // Dust from integer division goes back to the reserve it was collected into
+        let dust = fees
+            .saturating_sub(distributed)
+            .saturating_sub(withheld)
+            .saturating_sub(protocol_share);
+        let reserve = self.get_liquidity(asset.clone());
+        self.set_liquidity(asset.clone(), reserve.saturating_add(dust));
+        self.lp_fees_accumulated.set(asset, withheld);
+
+        distributed
     }
 
     /// Get all LP positions (for get_lp_positions function)
@@ -679,12 +2118,39 @@ impl Portfolio {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 #[contracttype]
 pub struct Metrics {
     pub trades_executed: u32,
     pub failed_orders: u32,
     pub balances_updated: u32,
+    /// Breakdown of `failed_orders` by reason; failures with other causes count only in the total
+    pub failed_paused: u32,
+    pub failed_invalid_pair: u32,
+    pub failed_zero_amount: u32,
+    pub failed_rate_limited: u32,
+}
+
+/// Why a non-panicking order was rejected
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum OrderFailure {
+    Paused,
+    InvalidPair,
+    ZeroAmount,
+    RateLimited,
+    Blacklisted,
+    NoLiquidity,
+    VolumeCapReached,
+    SlippageProtectionRequired,
+    InsufficientBalance,
+    LowConfidence,
+    SandwichBlocked,
+    StalePrice,
+    InvalidPrice,
+    SlippageExceeded,
+    /// Any other swap error
+    Other,
 }
 
 
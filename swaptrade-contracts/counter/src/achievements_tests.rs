@@ -51,6 +51,79 @@ mod badge_achievement_tests {
         assert!(portfolio.has_badge(&env, user.clone(), Badge::Trader));
     }
 
+    /// Test VeteranTrader badge unlocks at 100 trades
+    #[test]
+    fn test_veteran_trader_badge_at_hundred_trades() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        for _ in 0..99 {
+            portfolio.record_trade(&env, user.clone());
+        }
+        portfolio.check_and_award_badges(&env, user.clone());
+        assert!(portfolio.has_badge(&env, user.clone(), Badge::Trader));
+        assert!(!portfolio.has_badge(&env, user.clone(), Badge::VeteranTrader));
+
+        portfolio.record_trade(&env, user.clone());
+        portfolio.check_and_award_badges(&env, user.clone());
+        assert!(portfolio.has_badge(&env, user.clone(), Badge::VeteranTrader));
+        assert!(!portfolio.has_badge(&env, user.clone(), Badge::MasterTrader));
+    }
+
+    /// Test MasterTrader badge unlocks at 1000 trades
+    #[test]
+    fn test_master_trader_badge_at_thousand_trades() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        for _ in 0..999 {
+            portfolio.record_trade(&env, user.clone());
+        }
+        portfolio.check_and_award_badges(&env, user.clone());
+        assert!(!portfolio.has_badge(&env, user.clone(), Badge::MasterTrader));
+
+        portfolio.record_trade(&env, user.clone());
+        portfolio.check_and_award_badges(&env, user.clone());
+        assert!(portfolio.has_badge(&env, user.clone(), Badge::MasterTrader));
+    }
+
+    /// Test trade-count thresholds come from the configurable badge table
+    #[test]
+    fn test_trade_badge_thresholds_configurable() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        assert_eq!(portfolio.get_badge_threshold(Badge::VeteranTrader), 100);
+        portfolio.set_badge_threshold(Badge::VeteranTrader, 20);
+
+        for _ in 0..20 {
+            portfolio.record_trade(&env, user.clone());
+        }
+        portfolio.check_and_award_badges(&env, user.clone());
+        assert!(portfolio.has_badge(&env, user.clone(), Badge::VeteranTrader));
+    }
+
+    /// Test existing traders receive new trade-count badges during backfill
+    #[test]
+    fn test_backfill_awards_new_trade_badges() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        // Trades recorded without a badge check, as before the new tiers existed
+        for _ in 0..100 {
+            portfolio.record_trade(&env, user.clone());
+        }
+        assert!(portfolio.has_pending_trade_badges(&env));
+
+        portfolio.backfill_trade_badges(&env);
+        assert!(portfolio.has_badge(&env, user.clone(), Badge::VeteranTrader));
+        assert!(!portfolio.has_pending_trade_badges(&env));
+    }
+
     /// Test WealthBuilder badge unlocks at 10x starting balance
     #[test]
     fn test_wealth_builder_badge_at_10x_balance() {
@@ -199,8 +272,8 @@ mod badge_achievement_tests {
         
         let progress = portfolio.get_badge_progress(&env, user.clone());
         
-        // Should return progress for all 6 badges
-        assert_eq!(progress.len(), 6);
+        // Should return progress for all 8 badges
+        assert_eq!(progress.len(), 8);
         
         // Verify all badge types are present
         let mut has_first_trade = false;
@@ -209,6 +282,8 @@ mod badge_achievement_tests {
         let mut has_liquidity_provider = false;
         let mut has_diversifier = false;
         let mut has_consistency = false;
+        let mut has_veteran_trader = false;
+        let mut has_master_trader = false;
         
         for (badge, _, _) in progress.iter() {
             match badge {
@@ -218,6 +293,10 @@ mod badge_achievement_tests {
                 Badge::LiquidityProvider => has_liquidity_provider = true,
                 Badge::Diversifier => has_diversifier = true,
                 Badge::Consistency => has_consistency = true,
+                Badge::VeteranTrader => has_veteran_trader = true,
+                Badge::MasterTrader => has_master_trader = true,
+                Badge::LuckyDraw => panic!("LuckyDraw is random, not progress-based"),
+                Badge::EarlyAdopter => panic!("EarlyAdopter depends on arrival order, not progress"),
             }
         }
         
@@ -227,6 +306,8 @@ mod badge_achievement_tests {
         assert!(has_liquidity_provider);
         assert!(has_diversifier);
         assert!(has_consistency);
+        assert!(has_veteran_trader);
+        assert!(has_master_trader);
     }
 
     // ===== BADGE INDEPENDENCE TESTS =====
@@ -441,4 +522,107 @@ mod badge_achievement_tests {
         assert!(!portfolio.has_badge(&env, user1.clone(), Badge::LiquidityProvider));
         assert!(portfolio.has_badge(&env, user2.clone(), Badge::LiquidityProvider));
     }
+
+    /// The badge count agrees with the full badge list and ignores re-awards
+    #[test]
+    fn test_badge_count_matches_badge_list() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let none = TestAddress::generate(&env);
+        let one = TestAddress::generate(&env);
+        let three = TestAddress::generate(&env);
+
+        portfolio.award_badge(&env, one.clone(), Badge::FirstTrade);
+        portfolio.award_badge(&env, one.clone(), Badge::FirstTrade);
+
+        portfolio.award_badge(&env, three.clone(), Badge::FirstTrade);
+        portfolio.award_badge(&env, three.clone(), Badge::LiquidityProvider);
+        portfolio.award_badge(&env, three.clone(), Badge::Diversifier);
+
+        for (user, expected) in [(none, 0u32), (one, 1), (three, 3)] {
+            assert_eq!(portfolio.get_user_badge_count(user.clone()), expected);
+            assert_eq!(portfolio.get_user_badges(&env, user).len(), expected);
+        }
+    }
+
+    /// Only the first traders within the cutoff become early adopters; raising it backfills
+    #[test]
+    fn test_early_adopter_badge_within_cutoff() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let usdc = Asset::Custom(soroban_sdk::symbol_short!("USDCSIM"));
+        portfolio.set_early_adopter_cutoff(&env, 2);
+
+        let users = [
+            TestAddress::generate(&env),
+            TestAddress::generate(&env),
+            TestAddress::generate(&env),
+        ];
+        for user in users.iter() {
+            portfolio.mint(&env, Asset::XLM, user.clone(), 1000);
+            portfolio.transfer_asset(&env, Asset::XLM, usdc.clone(), user.clone(), 100);
+            portfolio.record_trade(&env, user.clone());
+        }
+
+        assert!(portfolio.has_badge(&env, users[0].clone(), Badge::EarlyAdopter));
+        assert!(portfolio.has_badge(&env, users[1].clone(), Badge::EarlyAdopter));
+        assert!(!portfolio.has_badge(&env, users[2].clone(), Badge::EarlyAdopter));
+
+        portfolio.set_early_adopter_cutoff(&env, 3);
+        assert!(portfolio.has_badge(&env, users[2].clone(), Badge::EarlyAdopter));
+    }
+
+    /// Badges come back in enum declaration order, not award order
+    #[test]
+    fn test_user_badges_in_canonical_order() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        for badge in [
+            Badge::EarlyAdopter,
+            Badge::LiquidityProvider,
+            Badge::LuckyDraw,
+            Badge::FirstTrade,
+            Badge::Trader,
+        ] {
+            portfolio.award_badge(&env, user.clone(), badge);
+        }
+
+        let badges = portfolio.get_user_badges(&env, user);
+        let expected = [
+            Badge::FirstTrade,
+            Badge::Trader,
+            Badge::LiquidityProvider,
+            Badge::LuckyDraw,
+            Badge::EarlyAdopter,
+        ];
+        assert_eq!(badges.len() as usize, expected.len());
+        for (i, badge) in expected.iter().enumerate() {
+            assert_eq!(badges.get(i as u32).unwrap(), *badge);
+        }
+    }
+
+    /// A held badge's fee discount comes off the tier fee, best discount only, floored at zero
+    #[test]
+    fn test_badge_fee_discount_lowers_swap_fee() {
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user = TestAddress::generate(&env);
+
+        portfolio.set_badge_fee_discount(Badge::WealthBuilder, 5);
+        portfolio.set_badge_fee_discount(Badge::LiquidityProvider, 3);
+
+        // Novice tier fee, no badges yet
+        assert_eq!(portfolio.swap_fee_bps(&env, user.clone()), 30);
+
+        portfolio.award_badge(&env, user.clone(), Badge::LiquidityProvider);
+        assert_eq!(portfolio.swap_fee_bps(&env, user.clone()), 27);
+
+        portfolio.award_badge(&env, user.clone(), Badge::WealthBuilder);
+        assert_eq!(portfolio.swap_fee_bps(&env, user.clone()), 25);
+
+        portfolio.set_badge_fee_discount(Badge::WealthBuilder, 50);
+        assert_eq!(portfolio.swap_fee_bps(&env, user.clone()), 0);
+    }
 }
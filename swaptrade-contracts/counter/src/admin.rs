@@ -1,16 +1,80 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{Address, Env, Map};
 
 use crate::errors::SwapTradeError;
-use crate::storage::ADMIN_KEY;
+use crate::storage::{ADMIN_KEY, BLACKLIST_KEY, DEPOSITS_FROZEN_KEY, INTEGRATORS_KEY, PAUSED_KEY};
+
+pub fn get_admin(env: &Env) -> Option<Address> {
+    env.storage().persistent().get::<_, Address>(&ADMIN_KEY)
+}
 
 pub fn is_admin(env: &Env, user: &Address) -> bool {
+    get_admin(env).map(|admin| admin == *user).unwrap_or(false)
+}
+
+pub fn is_paused(env: &Env) -> bool {
+    env.storage()
+        .persistent()
+        .get::<_, bool>(&PAUSED_KEY)
+        .unwrap_or(false)
+}
+
+pub fn is_deposits_frozen(env: &Env) -> bool {
     env.storage()
         .persistent()
-        .get::<_, Address>(&ADMIN_KEY)
-        .map(|admin| admin == *user)
+        .get::<_, bool>(&DEPOSITS_FROZEN_KEY)
         .unwrap_or(false)
 }
 
+pub fn set_deposits_frozen(env: &Env, frozen: bool) {
+    env.storage().persistent().set(&DEPOSITS_FROZEN_KEY, &frozen);
+}
+
+pub fn is_blacklisted(env: &Env, user: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .get::<_, Map<Address, bool>>(&BLACKLIST_KEY)
+        .and_then(|list| list.get(user.clone()))
+        .unwrap_or(false)
+}
+
+pub fn set_blacklisted(env: &Env, user: &Address, blacklisted: bool) {
+    let mut list: Map<Address, bool> = env
+        .storage()
+        .persistent()
+        .get(&BLACKLIST_KEY)
+        .unwrap_or(Map::new(env));
+
+    if blacklisted {
+        list.set(user.clone(), true);
+    } else {
+        list.remove(user.clone());
+    }
+    env.storage().persistent().set(&BLACKLIST_KEY, &list);
+}
+
+pub fn is_integrator(env: &Env, integrator: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .get::<_, Map<Address, bool>>(&INTEGRATORS_KEY)
+        .and_then(|list| list.get(integrator.clone()))
+        .unwrap_or(false)
+}
+
+pub fn set_integrator(env: &Env, integrator: &Address, allowed: bool) {
+    let mut list: Map<Address, bool> = env
+        .storage()
+        .persistent()
+        .get(&INTEGRATORS_KEY)
+        .unwrap_or(Map::new(env));
+
+    if allowed {
+        list.set(integrator.clone(), true);
+    } else {
+        list.remove(integrator.clone());
+    }
+    env.storage().persistent().set(&INTEGRATORS_KEY, &list);
+}
+
 pub fn require_admin(env: &Env, caller: &Address) -> Result<(), SwapTradeError> {
     if is_admin(env, caller) {
         Ok(())
@@ -0,0 +1,267 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{symbol_short, testutils::Address as _, Address, Env, Vec};
+
+/// Install a fresh admin directly in storage; shared by the other test modules
+pub(crate) fn setup_admin(env: &Env, contract_id: &Address) -> Address {
+    let admin = Address::generate(env);
+    env.as_contract(contract_id, || {
+        env.storage().persistent().set(&ADMIN_KEY, &admin);
+    });
+    admin
+}
+
+#[test]
+fn test_get_admin_reflects_current_admin() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    assert_eq!(client.get_admin(), None);
+
+    let admin = setup_admin(&env, &contract_id);
+    assert_eq!(client.get_admin(), Some(admin));
+
+    let successor = setup_admin(&env, &contract_id);
+    assert_eq!(client.get_admin(), Some(successor));
+}
+
+#[test]
+fn test_pause_resume_report_previous_state() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    setup_admin(&env, &contract_id);
+
+    env.as_contract(&contract_id, || {
+        assert_eq!(pause_trading(env.clone()), Ok(false));
+        assert!(is_paused(&env));
+
+        // First resume changes state, the second is a no-op
+        assert_eq!(resume_trading(env.clone()), Ok(true));
+        assert_eq!(resume_trading(env.clone()), Ok(false));
+        assert!(!is_paused(&env));
+    });
+}
+
+#[test]
+fn test_blacklisted_user_is_blocked() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &1000);
+    client.mint(&usdc, &user, &1000);
+
+    client.blacklist_user(&user);
+    assert!(client.is_blacklisted(&user));
+
+    assert_eq!(client.try_mint(&xlm, &user, &100), Err(Ok(SwapTradeError::Blacklisted)));
+    assert_eq!(client.try_add_liquidity(&100, &100, &user), Err(Ok(SwapTradeError::Blacklisted)));
+    assert_eq!(client.try_remove_liquidity(&10, &user), Err(Ok(SwapTradeError::Blacklisted)));
+
+    // Balances are untouched
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+    assert_eq!(client.balance_of(&usdc, &user), 1000);
+}
+
+#[test]
+fn test_unblacklist_restores_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    client.blacklist_user(&user);
+    assert_eq!(client.try_mint(&xlm, &user, &100), Err(Ok(SwapTradeError::Blacklisted)));
+
+    client.unblacklist_user(&user);
+    assert!(!client.is_blacklisted(&user));
+    client.mint(&xlm, &user, &100);
+    assert_eq!(client.balance_of(&xlm, &user), 100);
+}
+
+#[test]
+fn test_fee_override_applies_for_permitted_caller() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+    client.mint(&xlm, &user, &100_000);
+
+    // 80 bps instead of the Novice tier's 30 bps
+    client.swap_with_fee_bps(&xlm, &usdc, &10_000, &user, &80);
+    let fees = env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.get_pool_stats().2
+    });
+    assert_eq!(fees, 80);
+
+    // Overrides above MAX_FEE_BPS are capped
+    client.swap_with_fee_bps(&xlm, &usdc, &10_000, &user, &5_000);
+    let fees = env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.get_pool_stats().2
+    });
+    assert_eq!(fees, 80 + 10_000 * trading::MAX_FEE_BPS as i128 / 10000);
+}
+
+#[test]
+fn test_integrator_whitelist_roundtrip() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let integrator = Address::generate(&env);
+    assert!(!client.is_integrator(&integrator));
+    client.set_integrator(&integrator, &true);
+    assert!(client.is_integrator(&integrator));
+    client.set_integrator(&integrator, &false);
+    assert!(!client.is_integrator(&integrator));
+}
+
+#[test]
+fn test_fee_override_rejected_for_unlisted_caller() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    // No admin configured and nobody whitelisted
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &1000);
+
+    let result = client.try_swap_with_fee_bps(&xlm, &usdc, &100, &user, &0);
+    assert_eq!(result, Err(Ok(SwapTradeError::NotIntegrator)));
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+}
+
+#[test]
+fn test_batch_mint_credits_all_entries() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let mut entries = Vec::new(&env);
+    let mut users = Vec::new(&env);
+    for i in 1..=5 {
+        let user = Address::generate(&env);
+        entries.push_back((user.clone(), i * 100));
+        users.push_back(user);
+    }
+
+    client.batch_mint(&xlm, &entries);
+
+    for (i, user) in users.iter().enumerate() {
+        assert_eq!(client.balance_of(&xlm, &user), (i as i128 + 1) * 100);
+    }
+    assert_eq!(client.get_total_supply(&xlm), 1500);
+    assert_eq!(client.get_metrics().balances_updated, 5);
+}
+
+#[test]
+fn test_total_supply_unchanged_by_internal_transfers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.mint(&xlm, &trader, &10_000);
+
+    // Deposits, swaps (fees included) and withdrawals only move tokens around
+    let lp_tokens = client.add_liquidity(&50_000, &50_000, &lp);
+    client.swap(&xlm, &usdc, &5_000, &trader);
+    client.remove_liquidity(&(lp_tokens / 2), &lp);
+    assert_eq!(client.get_total_supply(&xlm), 110_000);
+    assert_eq!(client.get_total_supply(&usdc), 100_000);
+
+    client.burn(&xlm, &trader, &1_000);
+    assert_eq!(client.get_total_supply(&xlm), 109_000);
+}
+
+#[test]
+fn test_batch_mint_rejects_negative_amount() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let good = Address::generate(&env);
+    let bad = Address::generate(&env);
+    let mut entries = Vec::new(&env);
+    entries.push_back((good.clone(), 100));
+    entries.push_back((bad, -1));
+
+    let result = client.try_batch_mint(&xlm, &entries);
+    assert_eq!(result, Err(Ok(SwapTradeError::InvalidAmount)));
+
+    // Nothing was minted
+    assert_eq!(client.balance_of(&xlm, &good), 0);
+}
+
+#[test]
+fn test_frozen_deposits_still_allow_swaps_and_removals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &200_000);
+    client.mint(&usdc, &lp, &200_000);
+    let lp_tokens = client.add_liquidity(&100_000, &100_000, &lp);
+
+    client.freeze_deposits();
+    assert!(client.is_deposits_frozen());
+    let result = client.try_add_liquidity(&1_000, &1_000, &lp);
+    assert_eq!(result, Err(Ok(SwapTradeError::DepositsFrozen)));
+
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &5_000);
+    let out = client.swap(&xlm, &usdc, &1_000, &trader);
+    assert!(out > 0);
+
+    let (xlm_out, usdc_out) = client.remove_liquidity(&(lp_tokens / 2), &lp);
+    assert!(xlm_out > 0 && usdc_out > 0);
+
+    client.unfreeze_deposits();
+    assert!(!client.is_deposits_frozen());
+    assert!(client.add_liquidity(&1_000, &1_000, &lp) > 0);
+}
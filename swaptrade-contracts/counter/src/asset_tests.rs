@@ -0,0 +1,115 @@
+#![cfg(test)]
+
+use super::*;
+use crate::admin_tests::setup_admin;
+use soroban_sdk::{symbol_short, testutils::Address as _, Address, Env, String};
+
+#[test]
+fn test_asset_metadata_roundtrip() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let token = symbol_short!("GOLD");
+    let name = String::from_str(&env, "Gold Token");
+    client.set_asset_metadata(&token, &4, &name, &0);
+
+    let metadata = client.get_asset_metadata(&token).unwrap();
+    assert_eq!(metadata.decimals, 4);
+    assert_eq!(metadata.display_name, name);
+    assert_eq!(metadata.transfer_fee_bps, 0);
+}
+
+#[test]
+fn test_asset_metadata_unregistered_is_none() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    assert_eq!(client.get_asset_metadata(&symbol_short!("NOPE")), None);
+}
+
+#[test]
+fn test_fee_on_transfer_reserves_reflect_net_received() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    let lp = Address::generate(&env);
+
+    // USDCSIM behaves as a token that burns 1% on every transfer
+    client.set_asset_metadata(&usdc, &7, &String::from_str(&env, "USD Coin (sim)"), &100);
+
+    client.mint(&xlm, &lp, &10_000);
+    client.mint(&usdc, &lp, &10_000);
+    client.add_liquidity(&10_000, &10_000, &lp);
+
+    // The sender pays the full amount but the pool only holds what arrived
+    assert_eq!(client.balance_of(&usdc, &lp), 0);
+    env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        assert_eq!(portfolio.get_liquidity(Asset::XLM), 10_000);
+        assert_eq!(portfolio.get_liquidity(Asset::Custom(usdc.clone())), 9_900);
+    });
+}
+
+#[test]
+fn test_register_asset_rejected_past_max_assets() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    client.set_max_assets(&2);
+    let name = String::from_str(&env, "Token");
+    client.set_asset_metadata(&symbol_short!("GOLD"), &7, &name, &0);
+    client.set_asset_metadata(&symbol_short!("SILVER"), &7, &name, &0);
+    assert_eq!(client.get_registered_asset_count(), 2);
+
+    assert_eq!(
+        client.try_set_asset_metadata(&symbol_short!("BRONZE"), &7, &name, &0),
+        Err(Ok(SwapTradeError::TooManyAssets))
+    );
+    assert_eq!(client.get_registered_asset_count(), 2);
+    assert_eq!(client.get_asset_metadata(&symbol_short!("BRONZE")), None);
+
+    // Updating an already-registered asset is still allowed
+    client.set_asset_metadata(&symbol_short!("GOLD"), &4, &name, &0);
+    assert_eq!(client.get_asset_metadata(&symbol_short!("GOLD")).unwrap().decimals, 4);
+}
+
+#[test]
+fn test_reserve_cap_limits_deposits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &10_000);
+    client.mint(&usdc, &lp, &10_000);
+
+    client.set_reserve_cap(&xlm, &5_000);
+    assert_eq!(client.get_reserve_cap(&xlm), 5_000);
+    assert_eq!(client.get_reserve_cap(&usdc), 0);
+
+    client.add_liquidity(&4_000, &4_000, &lp);
+    assert_eq!(
+        client.try_add_liquidity(&2_000, &2_000, &lp),
+        Err(Ok(SwapTradeError::ReserveCapExceeded))
+    );
+
+    // Filling up to the cap exactly is allowed
+    client.add_liquidity(&1_000, &1_000, &lp);
+    assert_eq!(client.balance_of(&xlm, &lp), 5_000);
+}
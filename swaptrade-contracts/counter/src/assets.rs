@@ -0,0 +1,86 @@
+use soroban_sdk::{contracttype, Env, String, Symbol, Vec};
+
+/// Display metadata for an asset, consumed by UIs
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetMetadata {
+    /// Number of decimal places used when displaying amounts
+    pub decimals: u32,
+    /// Human-readable asset name
+    pub display_name: String,
+    /// Fee the token itself takes on every transfer, in bps (0 for ordinary tokens)
+    pub transfer_fee_bps: u32,
+}
+
+#[contracttype]
+pub enum AssetKey {
+    Metadata(Symbol),
+    Registered,
+    MaxAssets,
+    ReserveCap(Symbol),
+}
+
+pub fn set_metadata(env: &Env, symbol: Symbol, metadata: &AssetMetadata) {
+    env.storage().instance().set(&AssetKey::Metadata(symbol), metadata);
+}
+
+pub fn get_metadata(env: &Env, symbol: Symbol) -> Option<AssetMetadata> {
+    env.storage().instance().get(&AssetKey::Metadata(symbol))
+}
+
+/// Amount actually received when `amount` of `symbol` is transferred, after its transfer fee
+pub fn net_received(env: &Env, symbol: &Symbol, amount: i128) -> i128 {
+    match get_metadata(env, symbol.clone()) {
+        Some(metadata) if metadata.transfer_fee_bps > 0 => {
+            amount - (amount * metadata.transfer_fee_bps as i128) / 10000
+        }
+        _ => amount,
+    }
+}
+
+/// Maximum number of distinct registered assets (0 = unlimited)
+pub fn get_max_assets(env: &Env) -> u32 {
+    env.storage().instance().get(&AssetKey::MaxAssets).unwrap_or(0)
+}
+
+pub fn set_max_assets(env: &Env, max: u32) {
+    env.storage().instance().set(&AssetKey::MaxAssets, &max);
+}
+
+/// Whether registering `symbol` would exceed the asset limit; already-registered symbols never do
+pub fn would_exceed_limit(env: &Env, symbol: &Symbol) -> bool {
+    let max = get_max_assets(env);
+    let registered = registered_assets(env);
+    max > 0 && !registered.contains(symbol) && registered.len() >= max
+}
+
+/// Maximum pool reserve allowed for an asset (0 = uncapped)
+pub fn get_reserve_cap(env: &Env, symbol: Symbol) -> i128 {
+    env.storage().instance().get(&AssetKey::ReserveCap(symbol)).unwrap_or(0)
+}
+
+pub fn set_reserve_cap(env: &Env, symbol: Symbol, cap: i128) {
+    env.storage().instance().set(&AssetKey::ReserveCap(symbol), &cap);
+}
+
+/// Whether a reserve of `new_reserve` would exceed the asset's cap
+pub fn exceeds_reserve_cap(env: &Env, symbol: Symbol, new_reserve: i128) -> bool {
+    let cap = get_reserve_cap(env, symbol);
+    cap > 0 && new_reserve > cap
+}
+
+/// Add a symbol to the registered-asset list (no-op if already present)
+pub fn register(env: &Env, symbol: Symbol) {
+    let mut registered = registered_assets(env);
+    if !registered.contains(&symbol) {
+        registered.push_back(symbol);
+        env.storage().instance().set(&AssetKey::Registered, &registered);
+    }
+}
+
+pub fn registered_assets(env: &Env) -> Vec<Symbol> {
+    env.storage()
+        .instance()
+        .get(&AssetKey::Registered)
+        .unwrap_or(Vec::new(env))
+}
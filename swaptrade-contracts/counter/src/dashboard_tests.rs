@@ -56,6 +56,30 @@ mod dashboard_query_tests {
         assert!(portfolio.get_active_users_count() >= 2);
     }
 
+    /// Test get_daily_active_users counts unique traders and resets on the next UTC day
+    #[test]
+    fn test_daily_active_users_resets_on_day_rollover() {
+        use soroban_sdk::testutils::Ledger;
+
+        let env = Env::default();
+        let mut portfolio = Portfolio::new(&env);
+        let user1 = TestAddress::generate(&env);
+        let user2 = TestAddress::generate(&env);
+
+        env.ledger().set_timestamp(86_400 * 10 + 100);
+        portfolio.record_trade(&env, user1.clone());
+        portfolio.record_trade(&env, user2.clone());
+        env.ledger().set_timestamp(86_400 * 10 + 80_000);
+        portfolio.record_trade(&env, user1.clone());
+        assert_eq!(portfolio.get_daily_active_users(&env), 2);
+
+        // Next day: nobody has traded yet, then only user2 does
+        env.ledger().set_timestamp(86_400 * 11);
+        assert_eq!(portfolio.get_daily_active_users(&env), 0);
+        portfolio.record_trade(&env, user2);
+        assert_eq!(portfolio.get_daily_active_users(&env), 1);
+    }
+
     /// Test get_pool_stats returns correct tuple
     #[test]
     fn test_pool_stats() {
@@ -1,6 +1,7 @@
 #![cfg(test)]
 
 use super::*;
+use crate::admin_tests::setup_admin;
 use soroban_sdk::{symbol_short, Address, Env, Vec};
 
 /// Test 1: Insufficient Balance with Detailed Error Handling
@@ -316,6 +317,288 @@ fn test_fee_calculation_and_collection() {
     assert!(metrics_after.balances_updated > fees_before);
 }
 
+/// The same mint-then-trade activity reports different PnL under each mode
+#[test]
+fn test_pnl_mode_balance_delta_vs_realized_only() {
+    use crate::portfolio::{Asset, PnlMode, Portfolio};
+    use soroban_sdk::testutils::Address as _;
+
+    let env = Env::default();
+    let minter = Address::generate(&env);
+    let trader = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let activity = |portfolio: &mut Portfolio| {
+        // A large mint with one losing swap, and a small mint with one winning swap
+        portfolio.mint(&env, Asset::XLM, minter.clone(), 5_000);
+        portfolio.record_transaction(&env, minter.clone(), xlm.clone(), usdc.clone(), 1_000, 900);
+        portfolio.mint(&env, Asset::XLM, trader.clone(), 500);
+        portfolio.record_transaction(&env, trader.clone(), xlm.clone(), usdc.clone(), 100, 150);
+    };
+
+    let mut balance_delta = Portfolio::new(&env);
+    assert_eq!(balance_delta.get_pnl_mode(), PnlMode::BalanceDelta);
+    activity(&mut balance_delta);
+    assert_eq!(balance_delta.get_portfolio(&env, minter.clone()).1, 5_000);
+    assert_eq!(balance_delta.get_portfolio(&env, trader.clone()).1, 500);
+    assert_eq!(balance_delta.get_top_traders(1).get(0).unwrap().0, minter);
+
+    let mut realized = Portfolio::new(&env);
+    realized.set_pnl_mode(&env, PnlMode::RealizedOnly);
+    activity(&mut realized);
+    assert_eq!(realized.get_portfolio(&env, minter.clone()).1, -100);
+    assert_eq!(realized.get_portfolio(&env, trader.clone()).1, 50);
+    assert_eq!(realized.get_top_traders(1).get(0).unwrap().0, trader);
+
+    // Switching an existing portfolio's mode rebuilds the leaderboard
+    balance_delta.set_pnl_mode(&env, PnlMode::RealizedOnly);
+    assert_eq!(balance_delta.get_top_traders(2), realized.get_top_traders(2));
+}
+
+#[test]
+fn test_realized_pnl_values_both_legs_in_stable() {
+    use crate::portfolio::{Asset, PnlMode, Portfolio};
+    use soroban_sdk::testutils::Address as _;
+
+    let env = Env::default();
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let mut portfolio = Portfolio::new(&env);
+    portfolio.set_pnl_mode(&env, PnlMode::RealizedOnly);
+    // Pool rate: 1 XLM = 0.5 USDCSIM
+    portfolio.set_liquidity(Asset::XLM, 2_000);
+    portfolio.set_liquidity(portfolio.stable_asset(), 1_000);
+
+    // 1_000 XLM is worth 500 USDCSIM, so receiving 450 is a loss of 50, not a loss of 550
+    portfolio.record_transaction(&env, seller.clone(), xlm.clone(), usdc.clone(), 1_000, 450);
+    assert_eq!(portfolio.get_portfolio(&env, seller).1, -50);
+
+    // 250 XLM received for 100 USDCSIM is worth 125, a gain of 25
+    portfolio.record_transaction(&env, buyer.clone(), usdc, xlm, 100, 250);
+    assert_eq!(portfolio.get_portfolio(&env, buyer).1, 25);
+}
+
+/// A swap routed to another address is kept in history but is neither a gain nor a loss
+#[test]
+fn test_routed_transaction_excluded_from_realized_pnl() {
+    use soroban_sdk::testutils::Address as _;
+
+    let env = Env::default();
+    let user = Address::generate(&env);
+    let router = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let mut portfolio = Portfolio::new(&env);
+    portfolio.set_pnl_mode(&env, PnlMode::RealizedOnly);
+    portfolio.set_liquidity(Asset::XLM, 2_000);
+    portfolio.set_liquidity(portfolio.stable_asset(), 1_000);
+
+    portfolio.record_routed_transaction(&env, user.clone(), xlm, usdc, 1_000, 450, router.clone());
+    assert_eq!(portfolio.get_portfolio(&env, user.clone()).1, 0);
+
+    let history = portfolio.get_user_transactions(&env, user, 10);
+    assert_eq!(history.len(), 1);
+    let tx = history.get(0).unwrap();
+    assert_eq!((tx.from_amount, tx.to_amount), (1_000, 450));
+    assert_eq!(tx.recipient, Some(router));
+}
+
+/// Rebuilding from a candidate list re-ranks a leaderboard left stale by untracked PnL changes
+#[test]
+fn test_rebuild_leaderboard_from_candidates() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let a = Address::generate(&env);
+    let b = Address::generate(&env);
+    let c = Address::generate(&env);
+    for (user, amount) in [(a.clone(), 100), (b.clone(), 200), (c.clone(), 300)] {
+        client.mint(&symbol_short!("XLM"), &user, &amount);
+    }
+
+    // Debits change PnL without touching the leaderboard, leaving c stale at the top
+    let leaderboard = || {
+        env.as_contract(&contract_id, || {
+            let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+            portfolio.get_top_traders(10)
+        })
+    };
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.debit(&env, Asset::XLM, c.clone(), 250);
+        env.storage().instance().set(&(), &portfolio);
+    });
+    assert_eq!(leaderboard().get(0).unwrap(), (c.clone(), 300));
+
+    let mut candidates = Vec::new(&env);
+    candidates.push_back(a.clone());
+    candidates.push_back(c.clone());
+    candidates.push_back(b.clone());
+    client.rebuild_leaderboard_from(&candidates);
+
+    let rebuilt = leaderboard();
+    assert_eq!(rebuilt.len(), 3);
+    assert_eq!(rebuilt.get(0).unwrap(), (b, 200));
+    assert_eq!(rebuilt.get(1).unwrap(), (a, 100));
+    assert_eq!(rebuilt.get(2).unwrap(), (c, 50));
+}
+
+/// Fees are attributed to each pair independently of direction
+#[test]
+fn test_fee_revenue_by_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &10_000);
+    let out = client.swap(&xlm, &usdc, &5_000, &trader);
+    let first_fee = client.get_user_fees_paid(&trader);
+    assert!(first_fee > 0);
+    assert_eq!(client.get_fee_revenue_by_pair(&xlm, &usdc), first_fee);
+
+    // Swapping back books into the same pair, whichever way round it is asked for
+    client.swap(&usdc, &xlm, &out, &trader);
+    let total_fees = client.get_user_fees_paid(&trader);
+    assert!(total_fees > first_fee);
+    assert_eq!(client.get_fee_revenue_by_pair(&xlm, &usdc), total_fees);
+    assert_eq!(client.get_fee_revenue_by_pair(&usdc, &xlm), total_fees);
+    assert_eq!(client.get_fee_revenue_by_pair(&xlm, &symbol_short!("BTC")), 0);
+}
+
+/// Global trade counts over a window come from differencing trade checkpoints
+#[test]
+fn test_global_trades_in_window_from_checkpoints() {
+    use crate::portfolio::Portfolio;
+    use soroban_sdk::testutils::{Address as _, Ledger as _};
+
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    for (ts, trades) in [(100u64, 3u32), (200, 2), (300, 1)] {
+        env.ledger().set_timestamp(ts);
+        for i in 0..trades {
+            let user = if i % 2 == 0 { alice.clone() } else { bob.clone() };
+            portfolio.record_trade(&env, user);
+        }
+    }
+
+    // At t=300: the window [150, 300] excludes the 3 trades at t=100
+    assert_eq!(portfolio.get_global_trades_in_window(&env, 150), 3);
+    assert_eq!(portfolio.get_global_trades_in_window(&env, 1000), 6);
+    assert_eq!(portfolio.get_global_trades_in_window(&env, 0), 0);
+
+    env.ledger().set_timestamp(1_000);
+    assert_eq!(portfolio.get_global_trades_in_window(&env, 500), 0);
+}
+
+/// Equal-PnL traders rank the same way regardless of update order
+#[test]
+fn test_leaderboard_ties_break_deterministically() {
+    use crate::portfolio::{Asset, Portfolio};
+    use soroban_sdk::testutils::Address as _;
+
+    let env = Env::default();
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let mut first = Portfolio::new(&env);
+    first.mint(&env, Asset::XLM, alice.clone(), 1_000);
+    first.mint(&env, Asset::XLM, bob.clone(), 1_000);
+
+    let mut second = Portfolio::new(&env);
+    second.mint(&env, Asset::XLM, bob.clone(), 1_000);
+    second.mint(&env, Asset::XLM, alice.clone(), 1_000);
+
+    assert_eq!(first.get_top_traders(2), second.get_top_traders(2));
+
+    // Repeated zero-change updates don't reshuffle the tie
+    let before = first.get_top_traders(2);
+    first.mint(&env, Asset::XLM, alice.clone(), 0);
+    first.mint(&env, Asset::XLM, bob.clone(), 0);
+    assert_eq!(first.get_top_traders(2), before);
+
+    // With PnL still equal, the more active trader ranks first
+    first.record_trade(&env, bob.clone());
+    first.mint(&env, Asset::XLM, bob.clone(), 0);
+    assert_eq!(first.get_top_traders(1).get(0).unwrap().0, bob);
+}
+
+/// Utilization accrues per window relative to pool size
+#[test]
+fn test_utilization_history_across_windows() {
+    use crate::portfolio::{Portfolio, UTILIZATION_WINDOW_SECONDS};
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    portfolio.add_pool_liquidity(5_000, 5_000);
+
+    // Two swaps in the first window, one in the next, one two windows later
+    env.ledger().set_timestamp(10);
+    portfolio.record_utilization(&env, 500);
+    env.ledger().set_timestamp(20);
+    portfolio.record_utilization(&env, 500);
+    env.ledger().set_timestamp(UTILIZATION_WINDOW_SECONDS + 5);
+    portfolio.record_utilization(&env, 2_500);
+    env.ledger().set_timestamp(3 * UTILIZATION_WINDOW_SECONDS);
+    portfolio.record_utilization(&env, 100);
+
+    let history = portfolio.get_utilization_history(&env, 10);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(0).unwrap(), (0, 1_000));
+    assert_eq!(history.get(1).unwrap(), (UTILIZATION_WINDOW_SECONDS, 2_500));
+    assert_eq!(history.get(2).unwrap(), (3 * UTILIZATION_WINDOW_SECONDS, 100));
+
+    // The limit keeps the most recent windows
+    let recent = portfolio.get_utilization_history(&env, 1);
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent.get(0).unwrap().1, 100);
+}
+
+/// Fee revenue over a window is the difference between fee checkpoints
+#[test]
+fn test_fee_revenue_in_window_from_checkpoints() {
+    use crate::portfolio::Portfolio;
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+
+    for (ts, fee) in [(100u64, 30i128), (200, 20), (300, 10)] {
+        env.ledger().set_timestamp(ts);
+        portfolio.collect_fee(fee);
+        portfolio.record_fee_checkpoint(&env);
+    }
+
+    // At t=300: the window [150, 300] excludes the 30 collected at t=100
+    assert_eq!(portfolio.get_fee_revenue_in_window(&env, 150), 30);
+    assert_eq!(portfolio.get_fee_revenue_in_window(&env, 1000), 60);
+    assert_eq!(portfolio.get_fee_revenue_in_window(&env, 0), 0);
+
+    // Later with no new fees, the recent window is empty
+    env.ledger().set_timestamp(1_000);
+    assert_eq!(portfolio.get_fee_revenue_in_window(&env, 500), 0);
+}
+
 /// Test 11: Portfolio Statistics Updates
 /// Tests that portfolio statistics are correctly updated after trades
 #[test]
@@ -350,6 +633,8 @@ fn test_portfolio_statistics_updates() {
 /// Tests that trading activities properly trigger badge awards
 #[test]
 fn test_badge_system_integration_with_trading() {
+    use soroban_sdk::testutils::Ledger as _;
+
     let env = Env::default();
     let contract_id = env.register(CounterContract, ());
     let client = CounterContractClient::new(&env, &contract_id);
@@ -374,12 +659,1351 @@ fn test_badge_system_integration_with_trading() {
     let has_first_trade = client.has_badge(&user, &Badge::FirstTrade);
     assert!(has_first_trade);
 
-    // Perform more trades to test progression
+    // Perform more trades to test progression, an hour apart to stay within the swap rate limit
     for i in 0..9 {
+        env.ledger().set_timestamp((i as u64 + 1) * 3_600);
         client.swap(&xlm, &usdc, &(50 + i), &user);
     }
 
     // Should now have Trader badge (10+ trades)
     let final_badges = client.get_user_badges(&user);
     assert!(final_badges.len() >= 1);
-}
\ No newline at end of file
+}
+/// Default slippage stored per user is enforced by plain swap
+#[test]
+fn test_user_default_slippage_rejects_excessive_impact() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1000);
+    client.mint(&usdc, &lp, &1000);
+    client.add_liquidity(&1000, &1000, &lp);
+
+    client.mint(&xlm, &user, &1000);
+    client.set_default_slippage(&user, &50);
+    assert_eq!(client.get_default_slippage(&user), Some(50));
+
+    // 100 XLM into a 1000/1000 pool moves the price ~10%, far beyond 0.5%
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(result, Err(SwapTradeError::SlippageExceeded));
+    });
+
+    // An explicit min_out replaces the default tolerance rather than being raised to it
+    assert!(client.swap_with_min_out(&xlm, &usdc, &100, &user, &1) > 0);
+}
+
+/// Without a stored tolerance, plain swap still applies the contract default
+#[test]
+fn test_default_slippage_applies_when_unset() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1000);
+    client.mint(&usdc, &lp, &1000);
+    client.add_liquidity(&1000, &1000, &lp);
+
+    client.mint(&xlm, &user, &1000);
+    assert_eq!(client.get_default_slippage(&user), None);
+
+    // 600 XLM into a 1000/1000 pool lands ~37% below spot, beyond DEFAULT_SLIPPAGE_BPS
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 600, user.clone());
+        assert_eq!(result, Err(SwapTradeError::SlippageExceeded));
+    });
+
+    // Other swap paths carry no implicit tolerance
+    assert!(client.swap_with_deadline(&xlm, &usdc, &600, &user, &0) > 0);
+}
+
+/// Small swaps stay within the user's default tolerance
+#[test]
+fn test_user_default_slippage_allows_small_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    client.mint(&xlm, &user, &10_000);
+    client.set_default_slippage(&user, &100);
+
+    // 1000 XLM into a 1M/1M pool: ~0.4% below spot, inside the 1% tolerance
+    let out = client.swap(&xlm, &usdc, &1000, &user);
+    assert!(out > 0);
+}
+
+/// Directional volume tracks each swap direction separately
+#[test]
+fn test_directional_volume_by_swap_direction() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    client.mint(&xlm, &user, &10_000);
+    client.mint(&usdc, &user, &10_000);
+    assert_eq!(client.get_directional_volume(), (0, 0));
+
+    client.swap(&xlm, &usdc, &1000, &user);
+    client.swap(&xlm, &usdc, &500, &user);
+    client.swap(&usdc, &xlm, &300, &user);
+
+    assert_eq!(client.get_directional_volume(), (1500, 300));
+}
+
+/// Each user's directional volume only counts their own swaps
+#[test]
+fn test_user_direction_ratio_tracks_each_user() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    client.mint(&xlm, &user, &10_000);
+    client.mint(&usdc, &user, &10_000);
+    client.mint(&usdc, &other, &10_000);
+    assert_eq!(client.get_user_direction_ratio(&user), (0, 0));
+
+    client.swap(&xlm, &usdc, &1000, &user);
+    client.swap(&usdc, &xlm, &300, &user);
+    client.swap(&xlm, &usdc, &200, &user);
+    client.swap(&usdc, &xlm, &700, &other);
+
+    assert_eq!(client.get_user_direction_ratio(&user), (1200, 300));
+    assert_eq!(client.get_user_direction_ratio(&other), (0, 700));
+    assert_eq!(client.get_directional_volume(), (1200, 1000));
+}
+
+/// Swaps against an empty pool are rejected before any state changes
+#[test]
+fn test_swap_rejected_without_liquidity() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &user, &1000);
+
+    // Called directly: the client's generated `try_swap` clashes with the contract's own `try_swap`
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(result, Err(SwapTradeError::InsufficientLiquidity));
+
+        // The non-panicking path counts it as a failed order
+        let out = CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(out, 0);
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+    assert_eq!(client.balance_of(&usdc, &user), 0);
+    assert_eq!(client.get_metrics().failed_orders, 1);
+}
+
+/// A paused rejection in try_swap is counted under its own reason
+#[test]
+fn test_try_swap_counts_paused_failure() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &user, &1000);
+
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&PAUSED_KEY, &true);
+        let out = CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(out, 0);
+    });
+
+    let metrics = client.get_metrics();
+    assert_eq!(metrics.failed_orders, 1);
+    assert_eq!(metrics.failed_paused, 1);
+    assert_eq!(metrics.failed_invalid_pair, 0);
+    assert_eq!(metrics.failed_zero_amount, 0);
+    assert_eq!(metrics.failed_rate_limited, 0);
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+}
+
+/// Each try_swap rejection is kept in the user's failed-swap history with its reason
+#[test]
+fn test_try_swap_records_failed_swaps() {
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &user, &1000);
+
+    env.as_contract(&contract_id, || {
+        env.ledger().set_timestamp(100);
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone()), 0);
+        env.ledger().set_timestamp(200);
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), xlm.clone(), 50, user.clone()), 0);
+        env.ledger().set_timestamp(300);
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 0, user.clone()), 0);
+    });
+
+    let failed = client.get_user_failed_swaps(&user, &10);
+    assert_eq!(failed.len(), 3);
+    assert_eq!(
+        failed.get(0).unwrap(),
+        FailedSwap { timestamp: 100, from_token: xlm.clone(), to_token: usdc.clone(), amount: 100, reason: OrderFailure::NoLiquidity }
+    );
+    assert_eq!(failed.get(1).unwrap().reason, OrderFailure::InvalidPair);
+    assert_eq!(failed.get(1).unwrap().amount, 50);
+    assert_eq!(failed.get(2).unwrap().reason, OrderFailure::ZeroAmount);
+    assert_eq!(failed.get(2).unwrap().timestamp, 300);
+
+    // The limit keeps the most recent entries
+    let latest = client.get_user_failed_swaps(&user, &1);
+    assert_eq!(latest.get(0).unwrap().reason, OrderFailure::ZeroAmount);
+    assert_eq!(client.get_metrics().failed_orders, 3);
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+}
+
+/// Register the contract with a 100_000 / 100_000 pool
+fn setup_pool(env: &Env) -> (Address, CounterContractClient<'_>) {
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(env, &contract_id);
+    let lp = Address::generate(env);
+    client.mint(&symbol_short!("XLM"), &lp, &100_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+    (contract_id, client)
+}
+
+/// try_swap can't skip the slippage-protection requirement that `swap` enforces
+#[test]
+fn test_try_swap_requires_slippage_protection() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup_pool(&env);
+    setup_admin(&env, &contract_id);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &1_000);
+
+    client.set_require_slippage_protection(&true);
+    env.as_contract(&contract_id, || {
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone()), 0);
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), 1_000);
+    let failed = client.get_user_failed_swaps(&user, &1);
+    assert_eq!(failed.get(0).unwrap().reason, OrderFailure::SlippageProtectionRequired);
+}
+
+/// try_swap is blocked from back-running a large swap in the same ledger, like `swap`
+#[test]
+fn test_try_swap_sandwich_blocked() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup_pool(&env);
+    setup_admin(&env, &contract_id);
+    client.set_large_swap_threshold(&1_000);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &10_000);
+    client.mint(&usdc, &user, &10_000);
+
+    env.as_contract(&contract_id, || {
+        assert!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 5_000, user.clone()) > 0);
+        assert_eq!(CounterContract::try_swap(env.clone(), usdc.clone(), xlm.clone(), 5_000, user.clone()), 0);
+    });
+
+    assert!(client.balance_of(&usdc, &user) > 10_000);
+    let failed = client.get_user_failed_swaps(&user, &1);
+    assert_eq!(failed.get(0).unwrap().reason, OrderFailure::SandwichBlocked);
+}
+
+/// try_swap refuses a low-confidence oracle price, like `swap`
+#[test]
+fn test_try_swap_rejects_low_confidence_price() {
+    let env = Env::default();
+    let (contract_id, client) = setup_pool(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &1_000);
+
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), 1_000_000_000_000_000_000, Some(5000));
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone()), 0);
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), 1_000);
+    let failed = client.get_user_failed_swaps(&user, &1);
+    assert_eq!(failed.get(0).unwrap().reason, OrderFailure::LowConfidence);
+}
+
+/// try_swap checks the balance up front and records the shortfall instead of panicking
+#[test]
+fn test_try_swap_rejects_insufficient_balance() {
+    let env = Env::default();
+    let (contract_id, client) = setup_pool(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &user, &100);
+
+    env.as_contract(&contract_id, || {
+        assert_eq!(CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 200, user.clone()), 0);
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), 100);
+    assert_eq!(client.balance_of(&usdc, &user), 0);
+    let failed = client.get_user_failed_swaps(&user, &1);
+    assert_eq!(failed.get(0).unwrap().reason, OrderFailure::InsufficientBalance);
+}
+
+/// An opposing large swap by the same address in the same ledger is blocked
+#[test]
+fn test_large_swap_sandwich_blocked_in_same_ledger() {
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    setup_admin(&env, &contract_id);
+    client.set_large_swap_threshold(&1_000);
+
+    let lp = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+    client.mint(&xlm, &attacker, &100_000);
+    client.mint(&usdc, &attacker, &100_000);
+
+    env.ledger().set_sequence_number(10);
+    env.as_contract(&contract_id, || {
+        // Front-run leg goes through
+        let first = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 5_000, attacker.clone());
+        assert!(first.is_ok());
+
+        // Back-run leg in the same ledger is rejected
+        let second = CounterContract::swap(env.clone(), usdc.clone(), xlm.clone(), 5_000, attacker.clone());
+        assert_eq!(second, Err(SwapTradeError::SandwichBlocked));
+
+        // Small swaps are unaffected
+        let small = CounterContract::swap(env.clone(), usdc.clone(), xlm.clone(), 500, attacker.clone());
+        assert!(small.is_ok());
+    });
+
+    // The opposing leg is allowed in a later ledger
+    env.ledger().set_sequence_number(11);
+    env.as_contract(&contract_id, || {
+        let later = CounterContract::swap(env.clone(), usdc.clone(), xlm.clone(), 5_000, attacker.clone());
+        assert!(later.is_ok());
+    });
+}
+
+/// Lifetime fees paid equal the sum of each swap's fee
+#[test]
+fn test_user_fees_paid_accumulates_across_swaps() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+    client.mint(&xlm, &user, &100_000);
+
+    let total_fees = || {
+        env.as_contract(&contract_id, || {
+            let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+            portfolio.get_pool_stats().2
+        })
+    };
+
+    let mut expected = 0i128;
+    for amount in [10_000i128, 5_000, 3_333] {
+        let before = total_fees();
+        client.swap(&xlm, &usdc, &amount, &user);
+        expected += total_fees() - before;
+    }
+
+    assert!(expected > 0);
+    assert_eq!(client.get_user_fees_paid(&user), expected);
+    assert_eq!(client.get_user_fees_paid(&other), 0);
+}
+
+/// Fees round up and outputs round down, so k = x * y never shrinks from rounding
+#[test]
+fn test_rounding_never_reduces_pool_value() {
+    let reserve_in: u128 = 1_000_003;
+    let reserve_out: u128 = 999_989;
+    let k = reserve_in * reserve_out;
+
+    for amount in 1u128..=5_000 {
+        let fee = trading::fee_round_up(amount as i128, 30) as u128;
+        assert!(fee * 10000 >= amount * 30, "fee must not round down");
+
+        let out = trading::calculate_amount_out(amount, reserve_in, reserve_out);
+        let new_in = reserve_in + amount - fee;
+        let new_out = reserve_out - out;
+        assert!(new_in * new_out >= k, "pool value decreased for amount {}", amount);
+    }
+}
+
+/// Every preview field agrees with what the real swap then does
+#[test]
+fn test_preview_swap_matches_real_swap() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+    client.mint(&xlm, &user, &10_000);
+
+    let preview = client.preview_swap(&user, &xlm, &usdc, &10_000, &100);
+    let out = client.swap(&xlm, &usdc, &10_000, &user);
+
+    assert_eq!(preview.expected_out, out);
+    assert_eq!(preview.fee_amount, client.get_user_fees_paid(&user));
+
+    // Pool was 1:1, so the spot output equals the post-fee input
+    let swap_amount = 10_000 - preview.fee_amount;
+    assert_eq!(preview.price_impact_bps as i128, (swap_amount - out) * 10000 / swap_amount);
+    assert_eq!(preview.min_received, out * 9_900 / 10_000);
+    assert_eq!(preview.failure, None);
+}
+
+/// A swap that would be rejected previews as a failure with every amount zeroed
+#[test]
+fn test_preview_swap_reports_failure() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let empty = client.preview_swap(&user, &xlm, &usdc, &10_000, &0);
+    assert_eq!(empty.failure, Some(OrderFailure::NoLiquidity));
+    assert_eq!((empty.expected_out, empty.fee_amount, empty.min_received), (0, 0, 0));
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    assert_eq!(client.preview_swap(&user, &xlm, &xlm, &10_000, &0).failure, Some(OrderFailure::InvalidPair));
+    assert_eq!(client.preview_swap(&user, &xlm, &symbol_short!("BTC"), &10_000, &0).failure, Some(OrderFailure::InvalidPair));
+    let zero = client.preview_swap(&user, &xlm, &usdc, &0, &0);
+    assert_eq!(zero.failure, Some(OrderFailure::ZeroAmount));
+    assert_eq!(zero.price_impact_bps, 0);
+}
+
+/// The effective price falls short of the spot rate by exactly the fee and price impact
+#[test]
+fn test_effective_price_includes_fee_and_impact() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    // 1:1 pool, so the spot rate is exactly 1.0
+    let spot = RATE_PRECISION;
+    let effective = client.get_effective_price(&user, &xlm, &usdc, &10_000);
+    assert!(effective < spot);
+
+    let preview = client.preview_swap(&user, &xlm, &usdc, &10_000, &0);
+    assert_eq!(effective, preview.expected_out as u128 * RATE_PRECISION / 10_000);
+
+    // The whole shortfall is the fee plus the impact on the post-fee input
+    let swap_amount = 10_000 - preview.fee_amount;
+    let impact = swap_amount - preview.expected_out;
+    assert_eq!(spot - effective, (preview.fee_amount + impact) as u128 * RATE_PRECISION / 10_000);
+
+    assert_eq!(client.get_effective_price(&user, &xlm, &usdc, &0), 0);
+    // Unsupported pairs have no price, as with get_amount_out
+    assert_eq!(client.get_effective_price(&user, &xlm, &xlm, &10_000), 0);
+    assert_eq!(client.get_effective_price(&user, &xlm, &symbol_short!("BTC"), &10_000), 0);
+}
+
+/// The view-only quote matches what the following real swap pays out, in both directions
+#[test]
+fn test_get_amount_out_matches_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &2_000_000);
+    client.add_liquidity(&1_000_000, &2_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &50_000);
+
+    let quoted = client.get_amount_out(&user, &xlm, &usdc, &20_000);
+    assert!(quoted > 0);
+    assert_eq!(client.get_pair_reserves(&xlm, &usdc), (1_000_000, 2_000_000));
+    assert_eq!(client.swap(&xlm, &usdc, &20_000, &user), quoted);
+
+    let quoted_back = client.get_amount_out(&user, &usdc, &xlm, &10_000);
+    assert_eq!(client.swap(&usdc, &xlm, &10_000, &user), quoted_back);
+
+    // Unsupported pairs and non-positive amounts quote nothing
+    assert_eq!(client.get_amount_out(&user, &xlm, &xlm, &1_000), 0);
+    assert_eq!(client.get_amount_out(&user, &xlm, &symbol_short!("BTC"), &1_000), 0);
+    assert_eq!(client.get_amount_out(&user, &xlm, &usdc, &0), 0);
+    assert_eq!(client.get_amount_out(&user, &xlm, &usdc, &-5), 0);
+}
+
+/// Quotes use the given user's tier fee, not the caller's
+#[test]
+fn test_get_amount_out_uses_user_tier() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    // One large swap lifts the veteran out of the Novice tier
+    let veteran = Address::generate(&env);
+    client.mint(&xlm, &veteran, &50_000);
+    client.swap(&xlm, &usdc, &20_000, &veteran);
+    assert_eq!(client.get_user_tier(&veteran), UserTier::Trader);
+    let newcomer = Address::generate(&env);
+    assert_eq!(client.get_user_tier(&newcomer), UserTier::Novice);
+
+    let veteran_quote = client.get_amount_out(&veteran, &xlm, &usdc, &10_000);
+    let newcomer_quote = client.get_amount_out(&newcomer, &xlm, &usdc, &10_000);
+    assert!(veteran_quote > newcomer_quote);
+    assert_eq!(client.swap(&xlm, &usdc, &10_000, &veteran), veteran_quote);
+}
+
+/// Swapping native XLM gives the same result as the explicit XLM symbol swap
+#[test]
+fn test_swap_native_matches_symbol_swap() {
+    let setup = || {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(CounterContract, ());
+        let lp = Address::generate(&env);
+        let user = Address::generate(&env);
+        let client = CounterContractClient::new(&env, &contract_id);
+        client.mint(&symbol_short!("XLM"), &lp, &100_000);
+        client.mint(&symbol_short!("USDCSIM"), &lp, &100_000);
+        client.add_liquidity(&100_000, &100_000, &lp);
+        client.mint(&symbol_short!("XLM"), &user, &1_000);
+        (env, contract_id, user)
+    };
+    let usdc = symbol_short!("USDCSIM");
+
+    let (env_a, id_a, user_a) = setup();
+    let client_a = CounterContractClient::new(&env_a, &id_a);
+    let native_out = client_a.swap_native(&usdc, &600, &user_a);
+
+    let (env_b, id_b, user_b) = setup();
+    let client_b = CounterContractClient::new(&env_b, &id_b);
+    let symbol_out = client_b.swap(&symbol_short!("XLM"), &usdc, &600, &user_b);
+
+    assert_eq!(native_out, symbol_out);
+    assert_eq!(client_a.balance_of(&usdc, &user_a), client_b.balance_of(&usdc, &user_b));
+    // Only the requested amount is swapped
+    assert_eq!(client_a.balance_of(&symbol_short!("XLM"), &user_a), 400);
+
+    // The amount is validated like `swap`
+    assert_eq!(client_a.try_swap_native(&usdc, &0, &user_a), Err(Ok(SwapTradeError::InvalidAmount)));
+    assert_eq!(client_a.try_swap_native(&usdc, &1_000, &user_a), Err(Ok(SwapTradeError::InsufficientBalance)));
+
+    // Native XLM can only be swapped into the pool's other asset
+    assert_eq!(client_a.try_swap_native(&symbol_short!("XLM"), &100, &user_a), Err(Ok(SwapTradeError::InvalidSwapPair)));
+    assert_eq!(client_a.try_swap_native(&symbol_short!("BTC"), &100, &user_a), Err(Ok(SwapTradeError::InvalidSwapPair)));
+}
+
+/// A route quote equals executing each hop of the route in turn
+#[test]
+fn test_quote_route_matches_executed_route() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &60_000);
+    client.add_liquidity(&100_000, &60_000, &lp);
+    // Large enough that the first hop lifts the user out of the Novice tier before the second
+    client.mint(&xlm, &user, &2_000);
+
+    let path = Vec::from_array(&env, [xlm.clone(), usdc.clone(), xlm.clone()]);
+    let quoted = client.quote_route(&user, &path, &2_000);
+
+    let first_hop = client.swap(&xlm, &usdc, &2_000, &user);
+    assert_eq!(client.get_user_tier(&user), UserTier::Trader);
+    let second_hop = client.swap(&usdc, &xlm, &first_hop, &user);
+    assert_eq!(quoted, second_hop);
+
+    // A hop without a pool is an error
+    let missing = Vec::from_array(&env, [xlm.clone(), symbol_short!("BTC")]);
+    assert_eq!(client.try_quote_route(&user, &missing, &90), Err(Ok(SwapTradeError::InvalidSwapPair)));
+}
+
+/// The stored swap details match the pool's actual reserve movement
+#[test]
+fn test_last_swap_details_match_reserve_changes() {
+    use crate::portfolio::Portfolio;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+    client.mint(&xlm, &user, &10_000);
+    assert_eq!(client.get_last_swap_details(&user), None);
+
+    let reserves = || {
+        env.as_contract(&contract_id, || {
+            let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+            let (xlm_pool, usdc_pool, _) = portfolio.get_pool_stats();
+            (xlm_pool, usdc_pool)
+        })
+    };
+
+    let before = reserves();
+    let out = client.swap(&xlm, &usdc, &1_000, &user);
+    let after = reserves();
+
+    let details = client.get_last_swap_details(&user).unwrap();
+    assert_eq!(details.reserves_before, before);
+    assert_eq!(details.reserves_after, after);
+    assert_eq!(details.amount_in, 1_000);
+    assert_eq!(details.amount_out, out);
+    assert_eq!(details.fee_amount, 3);
+    assert_eq!(details.from_token, xlm);
+    assert_eq!(details.to_token, usdc);
+    assert_eq!(details.recipient, user);
+}
+
+/// `try_swap` records the same utilization and swap details as `swap`
+#[test]
+fn test_try_swap_records_same_bookkeeping_as_swap() {
+    let setup = || {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(CounterContract, ());
+        let client = CounterContractClient::new(&env, &contract_id);
+        let lp = Address::generate(&env);
+        client.mint(&symbol_short!("XLM"), &lp, &100_000);
+        client.mint(&symbol_short!("USDCSIM"), &lp, &100_000);
+        client.add_liquidity(&100_000, &100_000, &lp);
+        let user = Address::generate(&env);
+        client.mint(&symbol_short!("XLM"), &user, &10_000);
+        (env, contract_id, user)
+    };
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let (env_a, id_a, user_a) = setup();
+    let client_a = CounterContractClient::new(&env_a, &id_a);
+    let swapped = client_a.swap(&xlm, &usdc, &1_000, &user_a);
+
+    let (env_b, id_b, user_b) = setup();
+    let client_b = CounterContractClient::new(&env_b, &id_b);
+    assert_eq!(client_b.get_last_swap_details(&user_b), None);
+    let tried = client_b.try_swap(&xlm, &usdc, &1_000, &user_b);
+    assert_eq!(tried, swapped);
+
+    assert_eq!(client_b.get_utilization_history(&10), client_a.get_utilization_history(&10));
+    assert_eq!(client_b.get_utilization_history(&10).len(), 1);
+    let details = client_b.get_last_swap_details(&user_b).unwrap();
+    assert_eq!(details.amount_out, tried);
+    assert_eq!(details.recipient, user_b);
+    assert_eq!(details.reserves_after, client_a.get_last_swap_details(&user_a).unwrap().reserves_after);
+}
+
+/// The largest swap by input amount is tracked across users
+#[test]
+fn test_largest_trade_tracked() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+    assert_eq!(client.get_largest_trade(), None);
+
+    let small = Address::generate(&env);
+    let whale = Address::generate(&env);
+    let medium = Address::generate(&env);
+    for (user, amount) in [(small.clone(), 500i128), (whale.clone(), 5_000), (medium.clone(), 2_000)] {
+        client.mint(&xlm, &user, &(amount * 3));
+        client.swap(&xlm, &usdc, &amount, &user);
+    }
+
+    assert_eq!(client.get_largest_trade(), Some((whale, 5_000)));
+}
+
+/// A swap that moves an imbalanced pool back toward parity earns the rebalance rebate
+#[test]
+fn test_rebalancing_swap_pays_less_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    // Twice as much XLM as USDCSIM: selling USDCSIM for XLM rebalances
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &200_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&200_000, &100_000, &lp);
+
+    assert_eq!(client.get_rebalance_rebate(), 0);
+    client.set_rebalance_rebate(&10);
+
+    let seller = Address::generate(&env);
+    client.mint(&xlm, &seller, &30_000);
+    client.swap(&xlm, &usdc, &10_000, &seller);
+
+    let rebalancer = Address::generate(&env);
+    client.mint(&usdc, &rebalancer, &30_000);
+    client.swap(&usdc, &xlm, &10_000, &rebalancer);
+
+    // Both Novice (30 bps); the rebalancing swap gets 10 bps back
+    assert_eq!(client.get_last_swap_details(&seller).unwrap().fee_amount, 30);
+    assert_eq!(client.get_last_swap_details(&rebalancer).unwrap().fee_amount, 20);
+}
+
+/// Archiving returns the accrued volume, zeroes it and advances the epoch
+#[test]
+fn test_archive_and_reset_volume() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let trader = Address::generate(&env);
+    env.as_contract(&contract_id, || {
+        let mut portfolio = Portfolio::new(&env);
+        portfolio.mint(&env, Asset::XLM, trader.clone(), 5_000);
+        portfolio.transfer_asset(&env, Asset::XLM, Asset::Custom(symbol_short!("USDCSIM")), trader.clone(), 1_000);
+        portfolio.transfer_asset(&env, Asset::XLM, Asset::Custom(symbol_short!("USDCSIM")), trader.clone(), 500);
+        env.storage().instance().set(&(), &portfolio);
+    });
+    assert_eq!(client.get_volume_epoch(), 0);
+
+    assert_eq!(client.archive_and_reset_volume(), 1_500);
+    assert_eq!(client.get_volume_epoch(), 1);
+    env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        assert_eq!(portfolio.get_total_trading_volume(), 0);
+    });
+
+    // Nothing traded since the last archive
+    assert_eq!(client.archive_and_reset_volume(), 0);
+    assert_eq!(client.get_volume_epoch(), 2);
+}
+
+/// With badge checks deferred, swaps award nothing until `sync_badges` runs
+#[test]
+fn test_deferred_badge_checks_awarded_on_sync() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+
+    client.set_defer_badge_checks(&true);
+    assert!(client.get_defer_badge_checks());
+
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &5_000);
+    client.swap(&xlm, &usdc, &1_000, &trader);
+    assert!(!client.has_badge(&trader, &Badge::FirstTrade));
+
+    let badges = client.sync_badges(&trader);
+    assert!(badges.contains(&Badge::FirstTrade));
+    assert!(client.has_badge(&trader, &Badge::FirstTrade));
+}
+
+/// Swap statistics derive the average from volume and count, and report 0 before any swap
+#[test]
+fn test_swap_statistics_summary() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let empty = client.get_swap_statistics();
+    assert_eq!(empty.total_swaps, 0);
+    assert_eq!(empty.average_swap_size, 0);
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &10_000);
+    for amount in [1_000i128, 2_000, 600] {
+        client.swap(&xlm, &usdc, &amount, &trader);
+    }
+    // Rejected swaps count as failed orders, not as swaps
+    assert_eq!(client.try_swap(&xlm, &usdc, &0, &trader), 0);
+    assert_eq!(client.try_swap(&xlm, &xlm, &100, &trader), 0);
+
+    let stats = client.get_swap_statistics();
+    assert_eq!(stats.total_swaps, 3);
+    assert!(stats.total_fees > 0);
+    // Volume counts what reached the pool, so it and the fees make up everything swapped
+    assert_eq!(stats.total_volume + stats.total_fees, 3_600);
+    assert_eq!(stats.average_swap_size, stats.total_volume / 3);
+    assert_eq!(stats.failed_orders, 2);
+}
+
+/// Archiving the volume starts a new epoch but leaves the average swap size intact
+#[test]
+fn test_average_swap_size_survives_volume_archive() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &10_000);
+    client.swap(&xlm, &usdc, &1_000, &trader);
+    client.swap(&xlm, &usdc, &2_000, &trader);
+    let before = client.get_swap_statistics();
+
+    client.archive_and_reset_volume();
+    let after = client.get_swap_statistics();
+    assert_eq!(after.total_volume, 0);
+    assert_eq!(after.total_swaps, 2);
+    assert_eq!(after.average_swap_size, before.average_swap_size);
+
+    // The next epoch's swaps keep averaging over every swap so far
+    client.swap(&xlm, &usdc, &600, &trader);
+    let later = client.get_swap_statistics();
+    assert_eq!(later.average_swap_size, (before.total_volume + later.total_volume) / 3);
+}
+
+/// With slippage protection required, plain swaps fail and only explicit min_out swaps go through
+#[test]
+fn test_required_slippage_protection_rejects_plain_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &10_000);
+
+    client.set_require_slippage_protection(&true);
+    assert!(client.get_require_slippage_protection());
+    env.as_contract(&contract_id, || {
+        let plain = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 1_000, user.clone());
+        assert_eq!(plain, Err(SwapTradeError::SlippageProtectionRequired));
+    });
+    assert_eq!(client.balance_of(&xlm, &user), 10_000);
+    assert!(client.swap_with_min_out(&xlm, &usdc, &1_000, &user, &900) >= 900);
+
+    client.set_require_slippage_protection(&false);
+    assert!(client.swap(&xlm, &usdc, &1_000, &user) > 0);
+}
+
+/// A swap larger than the user's balance fails with a typed error and changes nothing
+#[test]
+fn test_swap_over_balance_returns_insufficient_balance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &1_000);
+    let reserves_before = client.get_pair_reserves(&xlm, &usdc);
+
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 5_000, user.clone());
+        assert_eq!(result, Err(SwapTradeError::InsufficientBalance));
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), 1_000);
+    assert_eq!(client.balance_of(&usdc, &user), 0);
+    assert_eq!(client.get_pair_reserves(&xlm, &usdc), reserves_before);
+    assert_eq!(client.get_user_transactions(&user, &10).len(), 0);
+}
+
+/// A swap executes once on the post-fee amount: the user pays exactly `amount` and receives the fee-adjusted quote
+#[test]
+fn test_swap_executes_once_on_post_fee_amount() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &10_000);
+
+    let (fee, expected_out) = env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        let fee_bps = trading::effective_swap_fee_bps(&env, &portfolio, user.clone(), &xlm, &usdc, 10_000);
+        let fee = trading::fee_round_up(10_000, fee_bps);
+        (fee, trading::quote_swap_out(&env, &portfolio, &xlm, &usdc, 10_000 - fee))
+    });
+    assert!(fee > 0);
+
+    // Spending the whole balance only works if the input is debited once
+    let out = client.swap(&xlm, &usdc, &10_000, &user);
+    assert_eq!(out, expected_out);
+    assert_eq!(client.balance_of(&xlm, &user), 0);
+    assert_eq!(client.balance_of(&usdc, &user), expected_out);
+}
+
+/// Builds a pool with the given reserves and a funded trader, inside the contract's context
+fn setup_reserves_pool(env: &Env, xlm_reserve: i128, usdc_reserve: i128) -> (Address, Address) {
+    let contract_id = env.register(CounterContract, ());
+    let trader = Address::generate(env);
+    env.as_contract(&contract_id, || {
+        let mut portfolio = Portfolio::new(env);
+        portfolio.set_liquidity(Asset::XLM, xlm_reserve);
+        portfolio.set_liquidity(portfolio.stable_asset(), usdc_reserve);
+        portfolio.mint(env, Asset::XLM, trader.clone(), 1_000_000);
+        portfolio.mint(env, portfolio.stable_asset(), trader.clone(), 1_000_000);
+        env.storage().instance().set(&(), &portfolio);
+    });
+    (contract_id, trader)
+}
+
+/// Swaps price against the reserves: k = x * y only grows by output rounding, never shrinks
+#[test]
+fn test_perform_swap_preserves_constant_product() {
+    let env = Env::default();
+    let (contract_id, trader) = setup_reserves_pool(&env, 1_000_000, 3_000_000);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        for (from, to, amount) in [(&xlm, &usdc, 10_000), (&usdc, &xlm, 45_000), (&xlm, &usdc, 777)] {
+            let x = portfolio.get_liquidity(Asset::XLM);
+            let y = portfolio.get_liquidity(portfolio.stable_asset());
+            let k_before = x * y;
+
+            let out = trading::perform_swap(&env, &mut portfolio, from.clone(), to.clone(), amount, trader.clone());
+
+            let x_after = portfolio.get_liquidity(Asset::XLM);
+            let y_after = portfolio.get_liquidity(portfolio.stable_asset());
+            let k_after = x_after * y_after;
+            assert!(out > 0);
+            assert!(k_after >= k_before);
+            // Rounding the output down keeps at most one unit of output in the pool
+            let input_reserve_after = if *from == xlm { x_after } else { y_after };
+            assert!(k_after - k_before < input_reserve_after);
+        }
+    });
+}
+
+/// Output depends on pool balance and falls as a trade pushes the price
+#[test]
+fn test_perform_swap_price_moves_with_imbalance() {
+    let env = Env::default();
+    let (contract_id, trader) = setup_reserves_pool(&env, 1_000_000, 2_000_000);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+
+        // XLM is scarce, so it buys about twice as much USDCSIM as USDCSIM buys XLM
+        let xlm_buys = trading::quote_swap_out(&env, &portfolio, &xlm, &usdc, 1_000);
+        let usdc_buys = trading::quote_swap_out(&env, &portfolio, &usdc, &xlm, 1_000);
+        assert!(xlm_buys > 1_900 && xlm_buys < 2_000);
+        assert!(usdc_buys > 490 && usdc_buys < 500);
+
+        // Repeated sells of XLM each get a worse price than the last
+        let first = perform_swap(&env, &mut portfolio, xlm.clone(), usdc.clone(), 50_000, trader.clone());
+        let second = perform_swap(&env, &mut portfolio, xlm.clone(), usdc.clone(), 50_000, trader.clone());
+        assert!(second < first);
+        assert!(first < 100_000);
+    });
+}
+
+/// A swap past its deadline is rejected untouched; at or before it, or with no deadline, it executes
+#[test]
+fn test_swap_with_deadline() {
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &3_000);
+
+    env.ledger().set_timestamp(1_000);
+    assert_eq!(
+        client.try_swap_with_deadline(&xlm, &usdc, &1_000, &user, &999),
+        Err(Ok(SwapTradeError::Expired))
+    );
+    assert_eq!(client.balance_of(&xlm, &user), 3_000);
+    assert_eq!(client.balance_of(&usdc, &user), 0);
+
+    assert!(client.swap_with_deadline(&xlm, &usdc, &1_000, &user, &1_000) > 0);
+    assert!(client.swap_with_deadline(&xlm, &usdc, &1_000, &user, &5_000) > 0);
+
+    // 0 means no deadline
+    env.ledger().set_timestamp(1_000_000);
+    assert!(client.swap_with_deadline(&xlm, &usdc, &1_000, &user, &0) > 0);
+    assert_eq!(client.balance_of(&xlm, &user), 0);
+}
+
+/// The receiver gets the output while the input comes out of the user's balance
+#[test]
+fn test_swap_with_receiver_credits_receiver() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    let router = Address::generate(&env);
+    client.mint(&xlm, &user, &5_000);
+
+    let quoted = client.get_amount_out(&user, &xlm, &usdc, &2_000);
+    let out = client.swap_with_receiver(&xlm, &usdc, &2_000, &user, &router);
+    assert_eq!(out, quoted);
+
+    assert_eq!(client.balance_of(&xlm, &user), 3_000);
+    assert_eq!(client.balance_of(&usdc, &user), 0);
+    assert_eq!(client.balance_of(&usdc, &router), out);
+    assert_eq!(client.balance_of(&xlm, &router), 0);
+
+    // The user's transaction records the actual output and where it went
+    let tx = client.get_user_transactions(&user, &1).get(0).unwrap();
+    assert_eq!((tx.from_amount, tx.to_amount), (2_000, out));
+    assert_eq!(tx.recipient, Some(router.clone()));
+    let details = client.get_last_swap_details(&user).unwrap();
+    assert_eq!(details.amount_out, out);
+    assert_eq!(details.recipient, router);
+}
+
+/// Once cumulative volume reaches the cap, the next swap is refused and nothing changes
+#[test]
+fn test_global_volume_cap_rejects_swaps_past_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &50_000);
+
+    assert_eq!(client.get_global_volume_cap(), 0);
+    client.set_global_volume_cap(&20_000);
+
+    client.swap(&xlm, &usdc, &10_000, &user);
+    client.swap(&xlm, &usdc, &10_000, &user);
+    // The cap counts gross input, fees included
+    assert_eq!(client.get_capped_volume(), 20_000);
+    let volume = client.get_swap_statistics().total_volume;
+
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 1_000, user.clone());
+        assert_eq!(result, Err(SwapTradeError::VolumeCapReached));
+    });
+    assert_eq!(client.balance_of(&xlm, &user), 30_000);
+    assert_eq!(client.get_swap_statistics().total_volume, volume);
+    assert_eq!(client.get_capped_volume(), 20_000);
+
+    // Archiving the reported volume does not lift the cap
+    assert_eq!(client.archive_and_reset_volume(), volume);
+    assert_eq!(client.try_swap(&xlm, &usdc, &1_000, &user), 0);
+    assert_eq!(client.get_capped_volume(), 20_000);
+
+    // Lifting the cap lets trading resume
+    client.set_global_volume_cap(&0);
+    client.swap(&xlm, &usdc, &1_000, &user);
+}
+
+/// Swaps through either entry point count toward the same hourly limit, and try_swap fails softly past it
+#[test]
+fn test_try_swap_respects_limit_exhausted_by_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &10_000);
+
+    let limit = client.get_swap_rate_limit(&user).limit;
+    for _ in 0..limit {
+        client.swap(&xlm, &usdc, &10, &user);
+    }
+    assert_eq!(client.get_swap_rate_limit(&user).used, limit);
+    let xlm_before = client.balance_of(&xlm, &user);
+    let usdc_before = client.balance_of(&usdc, &user);
+
+    env.as_contract(&contract_id, || {
+        let out = CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 10, user.clone());
+        assert_eq!(out, 0);
+    });
+
+    assert_eq!(client.balance_of(&xlm, &user), xlm_before);
+    assert_eq!(client.balance_of(&usdc, &user), usdc_before);
+    let metrics = client.get_metrics();
+    assert_eq!(metrics.failed_orders, 1);
+    assert_eq!(metrics.failed_rate_limited, 1);
+}
+
+/// A tier's daily free swaps skip the fee; later swaps pay it, and the allowance comes back the next day
+#[test]
+fn test_daily_free_swap_allowance() {
+    use soroban_sdk::testutils::Ledger as _;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &1_000_000);
+    client.mint(&usdc, &lp, &1_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    let user = Address::generate(&env);
+    client.mint(&xlm, &user, &50_000);
+    assert_eq!(client.get_free_swap_allowance(&client.get_user_tier(&user)), 0);
+    // Every tier gets the same allowance, so volume moving the user up a tier doesn't matter here
+    for tier in [UserTier::Novice, UserTier::Trader, UserTier::Expert, UserTier::Whale] {
+        client.set_free_swap_allowance(&tier, &2);
+    }
+
+    env.ledger().set_timestamp(86_400 * 3);
+    assert_eq!(client.get_free_swaps_remaining(&user), 2);
+    assert_eq!(client.preview_swap(&user, &xlm, &usdc, &10_000, &0).fee_amount, 0);
+    client.swap(&xlm, &usdc, &10_000, &user);
+    client.swap(&xlm, &usdc, &10_000, &user);
+    assert_eq!(client.get_user_fees_paid(&user), 0);
+    assert_eq!(client.get_free_swaps_remaining(&user), 0);
+
+    client.swap(&xlm, &usdc, &10_000, &user);
+    let charged = client.get_user_fees_paid(&user);
+    assert!(charged > 0);
+
+    // The next UTC day starts a fresh allowance
+    env.ledger().set_timestamp(86_400 * 4);
+    assert_eq!(client.get_free_swaps_remaining(&user), 2);
+    client.swap(&xlm, &usdc, &10_000, &user);
+    assert_eq!(client.get_user_fees_paid(&user), charged);
+}
+
+/// The same trade through swap and try_swap charges the same fee and pays the same output
+#[test]
+fn test_swap_and_try_swap_apply_identical_fees() {
+    let setup = || {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(CounterContract, ());
+        let client = CounterContractClient::new(&env, &contract_id);
+        let lp = Address::generate(&env);
+        let user = Address::generate(&env);
+        client.mint(&symbol_short!("XLM"), &lp, &1_000_000);
+        client.mint(&symbol_short!("USDCSIM"), &lp, &1_000_000);
+        client.add_liquidity(&1_000_000, &1_000_000, &lp);
+        client.mint(&symbol_short!("XLM"), &user, &20_000);
+        (env, contract_id, user)
+    };
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let (env_a, contract_a, user_a) = setup();
+    let client_a = CounterContractClient::new(&env_a, &contract_a);
+    let out_a = client_a.swap(&xlm, &usdc, &10_000, &user_a);
+
+    let (env_b, contract_b, user_b) = setup();
+    let client_b = CounterContractClient::new(&env_b, &contract_b);
+    let out_b = env_b.as_contract(&contract_b, || {
+        CounterContract::try_swap(env_b.clone(), xlm.clone(), usdc.clone(), 10_000, user_b.clone())
+    });
+
+    let fees_a = client_a.get_swap_statistics().total_fees;
+    assert!(fees_a > 0);
+    assert_eq!(client_b.get_swap_statistics().total_fees, fees_a);
+    assert_eq!(client_b.get_user_fees_paid(&user_b), client_a.get_user_fees_paid(&user_a));
+    assert_eq!(out_b, out_a);
+    assert_eq!(client_b.balance_of(&xlm, &user_b), client_a.balance_of(&xlm, &user_a));
+    assert_eq!(client_b.get_pair_reserves(&xlm, &usdc), client_a.get_pair_reserves(&xlm, &usdc));
+}
@@ -5,6 +5,29 @@ use soroban_sdk::contracterror;
 pub enum SwapTradeError {
     NotAdmin = 1,
     TradingPaused = 2,
+    Blacklisted = 3,
+    InsufficientLiquidity = 4,
+    NotIntegrator = 5,
+    SandwichBlocked = 6,
+    InvalidAmount = 7,
+    LowConfidence = 8,
+    TooManyAssets = 9,
+    ReserveCapExceeded = 10,
+    InvalidSwapPair = 11,
+    NoLiquidity = 12,
+    DepositTooSmall = 13,
+    MigrationRequired = 14,
+    AmountOverflow = 15,
+    DepositsFrozen = 16,
+    SlippageProtectionRequired = 17,
+    InsufficientBalance = 18,
+    TooManyPositions = 19,
+    Expired = 20,
+    VolumeCapReached = 21,
+    SlippageExceeded = 22,
+    RateLimited = 23,
+    StalePrice = 24,
+    InvalidPrice = 25,
 // counter/src/errors.rs
 use soroban_sdk::{contracterror};
 
@@ -1,12 +1,14 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, Address, Env, Symbol, Vec};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String, Symbol, Vec};
 
 // Bring in modules from parent directory
 mod events;
 mod admin;
+mod assets;
 mod errors;
 mod storage;
 mod trading;
+mod prng;
 
 use events::Events;
 
@@ -15,32 +17,49 @@ mod trading { include!("../trading.rs"); }
 pub mod migration;
 
 use portfolio::{Portfolio, Asset, LPPosition};
-pub use portfolio::{Badge, Metrics, Transaction};
+pub use portfolio::OrderFailure;
+pub use portfolio::LPDepositRecord;
+pub use portfolio::{ActivityEvent, Badge, FailedSwap, Metrics, PnlMode, SwapDetails, SwapStats, Transaction};
 pub use tiers::UserTier;
-pub use rate_limit::{RateLimiter, RateLimitStatus};
-use trading::perform_swap;
+pub use rate_limit::{RateLimitMode, RateLimiter, RateLimitStatus};
+pub use assets::AssetMetadata;
+pub use trading::{PricingMode, SwapPreview};
+use trading::{depth_to_rate, has_liquidity, perform_swap_to, pool_rate, round_trip_spread_bps, spot_quote, MAX_FEE_BPS, RATE_PRECISION};
 
 
-use crate::admin::require_admin;
+use crate::admin::{is_admin, is_blacklisted, is_deposits_frozen, is_paused, require_admin};
 use crate::errors::SwapTradeError;
+use crate::oracle::ContractError;
 use crate::storage::{ADMIN_KEY, PAUSED_KEY};
 
+/// Pause trading. Returns the previous paused state; the event is only emitted
+/// when trading was actually running.
 pub fn pause_trading(env: Env) -> Result<bool, SwapTradeError> {
     let caller = env.invoker();
     caller.require_auth();
     require_admin(&env, &caller)?;
 
-    env.storage().persistent().set(&PAUSED_KEY, &true);
-    Ok(true)
+    let was_paused = is_paused(&env);
+    if !was_paused {
+        env.storage().persistent().set(&PAUSED_KEY, &true);
+        Events::admin_paused(&env, caller, env.ledger().timestamp() as i64);
+    }
+    Ok(was_paused)
 }
 
+/// Resume trading. Returns the previous paused state; the event is only emitted
+/// when trading was actually paused.
 pub fn resume_trading(env: Env) -> Result<bool, SwapTradeError> {
     let caller = env.invoker();
     caller.require_auth();
     require_admin(&env, &caller)?;
 
-    env.storage().persistent().set(&PAUSED_KEY, &false);
-    Ok(true)
+    let was_paused = is_paused(&env);
+    if was_paused {
+        env.storage().persistent().set(&PAUSED_KEY, &false);
+        Events::admin_resumed(&env, caller, env.ledger().timestamp() as i64);
+    }
+    Ok(was_paused)
 }
 
 pub fn set_admin(env: Env, new_admin: Address) -> Result<(), SwapTradeError> {
@@ -63,7 +82,9 @@ use batch::{
 
 // Oracle imports
 use oracle::{set_stored_price, get_price_safe};
-pub const CONTRACT_VERSION: u32 = 1;
+pub const CONTRACT_VERSION: u32 = 2;
+pub const CONTRACT_VERSION_MINOR: u32 = 0;
+pub use migration::VersionInfo;
 
 #[contract]
 pub struct CounterContract;
@@ -71,24 +92,77 @@ pub struct CounterContract;
 #[contractimpl]
 impl CounterContract {
     /// Initialize the contract version. 
-    /// Should be called after deployment.
+    /// Should be called after deployment; fresh storage starts at the current version.
     pub fn initialize(env: Env) {
         if migration::get_stored_version(&env) == 0 {
             env.storage().instance().set(&Symbol::short("v_code"), &CONTRACT_VERSION);
         }
     }
 
+    /// Admin: set the symbol of the pool's stable asset (USDCSIM by default).
+    /// Only allowed during setup, before the pool holds any liquidity.
+    pub fn set_stable_symbol(env: Env, symbol: Symbol) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_stable_symbol(symbol);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the symbol of the pool's stable asset
+    pub fn get_stable_symbol(env: Env) -> Symbol {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.stable_symbol()
+    }
+
     /// Get the current contract version from storage
     pub fn get_contract_version(env: Env) -> u32 {
         migration::get_stored_version(&env)
     }
 
+    /// Whether storage must be migrated before version-dependent endpoints will run
+    pub fn migration_required(env: Env) -> bool {
+        migration::migration_required(&env)
+    }
+
+    /// Get the code's major/minor version and whether a migration is pending
+    pub fn get_version_info(env: Env) -> VersionInfo {
+        migration::get_version_info(&env)
+    }
+
+    /// Get the metrics snapshot taken at a version bump (the last state before leaving it)
+    pub fn get_metrics_snapshot(env: Env, version: u32) -> Option<Metrics> {
+        migration::get_metrics_snapshot(&env, version)
+    }
+
+    /// Describe the steps a pending `migrate` would perform (empty if nothing is pending)
+    pub fn describe_migration(env: Env) -> Vec<Symbol> {
+        migration::describe_migration(&env)
+    }
+
     /// Migrate contract data from V1 to V2
     pub fn migrate(env: Env) -> Result<(), u32> {
         migration::migrate_from_v1_to_v2(&env)
     }
 
-    pub fn mint(env: Env, token: Symbol, to: Address, amount: i128) {
+    pub fn mint(env: Env, token: Symbol, to: Address, amount: i128) -> Result<(), SwapTradeError> {
+        if is_blacklisted(&env, &to) {
+            return Err(SwapTradeError::Blacklisted);
+        }
+
         let mut portfolio: Portfolio = env
             .storage()
             .instance()
@@ -101,9 +175,94 @@ impl CounterContract {
             Asset::Custom(token.clone())
         };
 
-        portfolio.mint(&env, asset, to, amount);
+        let prior_supply = portfolio.get_total_supply(asset.clone());
+        portfolio.mint(&env, asset.clone(), to, amount);
+        debug_assert_eq!(
+            portfolio.get_total_supply(asset),
+            prior_supply + amount,
+            "Total supply invariant violated"
+        );
+
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Admin: mint `token` to many addresses at once (faucets, test setups)
+    /// Rejects the whole batch if any amount is negative or any recipient is blacklisted
+    pub fn batch_mint(env: Env, token: Symbol, entries: Vec<(Address, i128)>) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        for (to, amount) in entries.iter() {
+            if amount < 0 {
+                return Err(SwapTradeError::InvalidAmount);
+            }
+            if is_blacklisted(&env, &to) {
+                return Err(SwapTradeError::Blacklisted);
+            }
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let asset = if token == Symbol::short("XLM") {
+            Asset::XLM
+        } else {
+            Asset::Custom(token.clone())
+        };
+
+        portfolio.batch_mint(&env, asset, &entries);
+
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Burn tokens from the caller's balance, reducing the asset's total supply
+    pub fn burn(env: Env, token: Symbol, from: Address, amount: i128) -> Result<(), SwapTradeError> {
+        from.require_auth();
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let asset = if token == Symbol::short("XLM") {
+            Asset::XLM
+        } else {
+            Asset::Custom(token.clone())
+        };
+
+        let prior_supply = portfolio.get_total_supply(asset.clone());
+        portfolio.burn(&env, asset.clone(), from, amount);
+        debug_assert_eq!(
+            portfolio.get_total_supply(asset),
+            prior_supply - amount,
+            "Total supply invariant violated"
+        );
 
         env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the total issued supply of a token (minted minus burned)
+    pub fn get_total_supply(env: Env, token: Symbol) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let asset = if token == Symbol::short("XLM") {
+            Asset::XLM
+        } else {
+            Asset::Custom(token)
+        };
+        portfolio.get_total_supply(asset)
     }
 
     pub fn balance_of(env: Env, token: Symbol, user: Address) -> i128 {
@@ -127,105 +286,185 @@ impl CounterContract {
         Self::balance_of(env, token, owner)
     }
 
-    /// Swap tokens using simplified AMM (1:1 XLM <-> USDC-SIM)
-    pub fn swap(env: Env, from: Symbol, to: Symbol, amount: i128, user: Address) -> i128 {
-        let mut portfolio: Portfolio = env
+    /// Swap tokens against the XLM/USDC-SIM pool
+    /// Rejected with `InsufficientLiquidity` while either pool reserve is empty, and with
+    /// `SlippageExceeded` beyond the user's default slippage tolerance
+    pub fn swap(env: Env, from: Symbol, to: Symbol, amount: i128, user: Address) -> Result<i128, SwapTradeError> {
+        Self::execute_swap(env, from, to, amount, user.clone(), user, None, None)
+    }
+
+    /// Swap, rejecting the trade if it would pay out less than `min_out`
+    pub fn swap_with_min_out(
+        env: Env,
+        from: Symbol,
+        to: Symbol,
+        amount: i128,
+        user: Address,
+        min_out: i128,
+    ) -> Result<i128, SwapTradeError> {
+        Self::execute_swap(env, from, to, amount, user.clone(), user, None, Some(min_out))
+    }
+
+    /// Swap `user`'s input and credit the output to `receiver`, so a router or aggregator
+    /// contract can take delivery and continue the trade. Requires `user`'s auth.
+    /// The swap counts as `user`'s trade and its transaction records the output and `receiver`,
+    /// but it's left out of `user`'s realized PnL unless `receiver` is `user`.
+    pub fn swap_with_receiver(
+        env: Env,
+        from: Symbol,
+        to: Symbol,
+        amount: i128,
+        user: Address,
+        receiver: Address,
+    ) -> Result<i128, SwapTradeError> {
+        user.require_auth();
+
+        Self::execute_swap(env, from, to, amount, user, receiver, None, Some(0))
+    }
+
+    /// Swap, rejecting the trade with `Expired` once the ledger timestamp is past `deadline`.
+    /// A `deadline` of 0 disables the check. No default slippage tolerance is applied.
+    pub fn swap_with_deadline(
+        env: Env,
+        from: Symbol,
+        to: Symbol,
+        amount: i128,
+        user: Address,
+        deadline: u64,
+    ) -> Result<i128, SwapTradeError> {
+        if deadline != 0 && env.ledger().timestamp() > deadline {
+            return Err(SwapTradeError::Expired);
+        }
+        Self::execute_swap(env, from, to, amount, user.clone(), user, None, Some(0))
+    }
+
+    /// Swap `amount` of the native XLM credited to `user` into `to`. The contract has no
+    /// attached-payment mechanism, so native XLM deposits land in the internal XLM balance.
+    /// Otherwise checked and executed exactly like `swap` from XLM.
+    pub fn swap_native(env: Env, to: Symbol, amount: i128, user: Address) -> Result<i128, SwapTradeError> {
+        user.require_auth();
+
+        let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Get user's current tier for fee calculation and rate limiting
-        let user_tier = portfolio.get_user_tier(&env, user.clone());
-        
-        // Check rate limit before executing swap
-        if let Err(_limit_status) = RateLimiter::check_swap_limit(&env, &user, &user_tier) {
-            panic!("RATELIMIT");
+        // Native XLM only pairs with the pool's stable asset
+        if to != portfolio.stable_symbol() {
+            return Err(SwapTradeError::InvalidSwapPair);
         }
 
-        let fee_bps = user_tier.effective_fee_bps();
-
-        // Calculate fee amount (fee is collected on input amount)
-        let fee_amount = (amount * fee_bps as i128) / 10000;
-        let swap_amount = amount - fee_amount;
+        Self::execute_swap(env, symbol_short!("XLM"), to, amount, user.clone(), user, None, None)
+    }
 
-        // Collect the fee
-        if fee_amount > 0 {
-            // Deduct from user
-            let fee_asset = if from == symbol_short!("XLM") {
-                Asset::XLM
-            } else {
-                Asset::Custom(from.clone())
-            };
-            
-            // We need to use a mutable borrow of portfolio which we already have
-            portfolio.debit(&env, fee_asset, user.clone(), fee_amount);
-            portfolio.collect_fee(fee_amount);
+    /// Swap with an explicit fee (bps) instead of the user's tier fee, capped at `MAX_FEE_BPS`
+    /// Only callable by the admin or a whitelisted integrator
+    pub fn swap_with_fee_bps(
+        env: Env,
+        from: Symbol,
+        to: Symbol,
+        amount: i128,
+        user: Address,
+        fee_bps: u32,
+    ) -> Result<i128, SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        if !is_admin(&env, &caller) && !admin::is_integrator(&env, &caller) {
+            return Err(SwapTradeError::NotIntegrator);
         }
+        user.require_auth();
 
-        let out_amount = perform_swap(&env, &mut portfolio, from.clone(), to.clone(), swap_amount, user.clone());
+        Self::execute_swap(env, from, to, amount, user.clone(), user, Some(fee_bps.min(MAX_FEE_BPS)), Some(0))
+    }
 
-        let out_amount = perform_swap(&env, &mut portfolio, from, to, amount, user.clone());
+    /// Admin: add or remove an integrator allowed to set its own swap fee
+    pub fn set_integrator(env: Env, integrator: Address, allowed: bool) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
 
-        portfolio.record_trade(&env, user);
-        env.storage().instance().set(&(), &portfolio);
+        admin::set_integrator(&env, &integrator, allowed);
+        Ok(())
+    }
 
-        // Optional structured logging for successful swap
-        #[cfg(feature = "logging")]
-        {
-            use soroban_sdk::symbol_short;
-            env.events().publish(
-                (symbol_short!("swap")),
-                (amount, out_amount),
-            );
-        }
+    /// Admin: set the size at which swaps get anti-sandwich protection (0 disables it)
+    /// A large swap is rejected if the same address made an opposing large swap in the same ledger
+    pub fn set_large_swap_threshold(env: Env, threshold: i128) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        trading::set_large_swap_threshold(&env, threshold);
+        Ok(())
+    }
+
+    /// Admin: require every swap to carry a nonzero `min_out`; plain swaps are then rejected
+    /// with `SlippageProtectionRequired` and callers must use `swap_with_min_out`
+    pub fn set_require_slippage_protection(env: Env, required: bool) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        trading::set_require_slippage_protection(&env, required);
+        Ok(())
+    }
 
-        out_amount
+    /// Get whether swaps must carry a nonzero `min_out`
+    pub fn get_require_slippage_protection(env: Env) -> bool {
+        trading::requires_slippage_protection(&env)
+    }
+
+    /// Check whether an address is a whitelisted integrator
+    pub fn is_integrator(env: Env, integrator: Address) -> bool {
+        admin::is_integrator(&env, &integrator)
+    }
+
+    /// Set the caller's default slippage tolerance (bps) applied by plain `swap`
+    /// Without one, `DEFAULT_SLIPPAGE_BPS` applies
+    pub fn set_default_slippage(env: Env, user: Address, bps: u32) {
+        user.require_auth();
+        trading::set_user_slippage_bps(&env, &user, bps);
+    }
+
+    /// Get a user's default slippage tolerance, if set
+    pub fn get_default_slippage(env: Env, user: Address) -> Option<u32> {
+        trading::get_user_slippage_bps(&env, &user)
     }
 
     /// Non-panicking swap that counts failed orders and returns 0 on failure
     pub fn try_swap(env: Env, from: Symbol, to: Symbol, amount: i128, user: Address) -> i128 {
-        let mut portfolio: Portfolio = env
-            .storage()
-            .instance()
-            .get(&())
-            .unwrap_or_else(Portfolio::new);
-
-        let tokens_ok = (from == Symbol::short("XLM") || from == Symbol::short("USDC-SIM"))
-            && (to == Symbol::short("XLM") || to == Symbol::short("USDC-SIM"));
-        let pair_ok = from != to;
-        let amount_ok = amount > 0;
-
-        if !(tokens_ok && pair_ok && amount_ok) {
-            // Count failed order
-            portfolio.inc_failed_order();
-            env.storage().instance().set(&(), &portfolio);
-
-            #[cfg(feature = "logging")]
-            {
-                use soroban_sdk::symbol_short;
-                env.events().publish(
-                    (symbol_short!("swap_failed"), user.clone()),
-                    (from, to, amount),
-                );
-            }
-            return 0;
-        }
+        // Same guards as `swap`, plus the pause; a rejection is recorded instead of panicking
+        let result = if is_blacklisted(&env, &user) {
+            Err(SwapTradeError::Blacklisted)
+        } else if is_paused(&env) {
+            Err(SwapTradeError::TradingPaused)
+        } else {
+            Self::execute_swap(env.clone(), from.clone(), to.clone(), amount, user.clone(), user.clone(), None, None)
+        };
 
-    let out_amount = perform_swap(&env, &mut portfolio, from, to, amount, user.clone());
-    portfolio.record_trade(&env, user);
-    env.storage().instance().set(&(), &portfolio);
+        match result {
+            Ok(out_amount) => out_amount,
+            Err(err) => {
+                let mut portfolio: Portfolio = env
+                    .storage()
+                    .instance()
+                    .get(&())
+                    .unwrap_or_else(|| Portfolio::new(&env));
+                portfolio.record_failed_swap(&env, user.clone(), from.clone(), to.clone(), amount, Self::order_failure(err));
+                env.storage().instance().set(&(), &portfolio);
 
-        #[cfg(feature = "logging")]
-        {
-            use soroban_sdk::symbol_short;
-            env.events().publish(
-                (symbol_short!("swap")),
-                (amount, out_amount),
-            );
+                #[cfg(feature = "logging")]
+                {
+                    use soroban_sdk::symbol_short;
+                    env.events().publish(
+                        (symbol_short!("swap_failed"), user.clone()),
+                        (from, to, amount),
+                    );
+                }
+                0
+            }
         }
-
-        out_amount
     }
 
     /// Record a swap execution for a user
@@ -241,6 +480,35 @@ impl CounterContract {
         env.storage().instance().set(&(), &portfolio);
     }
 
+    /// Admin: choose whether reported PnL counts all balance changes or only swap results.
+    /// The leaderboard is rebuilt under the new mode.
+    pub fn set_pnl_mode(env: Env, mode: PnlMode) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_pnl_mode(&env, mode);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the active PnL mode (BalanceDelta by default)
+    pub fn get_pnl_mode(env: Env) -> PnlMode {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_pnl_mode()
+    }
+
     /// Get portfolio stats for a user (trade count, pnl)
     pub fn get_portfolio(env: Env, user: Address) -> (u32, i128) {
         let portfolio: Portfolio = env
@@ -263,132 +531,1175 @@ impl CounterContract {
         portfolio.get_metrics()
     }
 
-    /// Check if a user has earned a specific badge
-    pub fn has_badge(env: Env, user: Address, badge: Badge) -> bool {
+    /// Get total swaps, volume, fees, average swap size and failed orders
+    pub fn get_swap_statistics(env: Env) -> SwapStats {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
-            .unwrap_or_else(Portfolio::new);
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        portfolio.has_badge(&env, user, badge)
+        portfolio.get_swap_statistics()
     }
 
-    /// Get all badges earned by a user
-    pub fn get_user_badges(env: Env, user: Address) -> Vec<Badge> {
+    /// Get the total swap fees a user has paid
+    pub fn get_user_fees_paid(env: Env, user: Address) -> i128 {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
-            .unwrap_or_else(Portfolio::new);
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        portfolio.get_user_badges(&env, user)
+        portfolio.get_user_fees_paid(user)
     }
 
-    pub fn get_user_transactions(env: Env, user: Address, limit: u32) -> Vec<Transaction> {
+    /// Get swap fee revenue collected over the last `window_seconds`
+    pub fn get_fee_revenue_in_window(env: Env, window_seconds: u64) -> i128 {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        portfolio.get_user_transactions(&env, user, limit)
+        portfolio.get_fee_revenue_in_window(&env, window_seconds)
     }
 
-    /// Get the current tier for a user
-    pub fn get_user_tier(env: Env, user: Address) -> UserTier {
+    /// Get swap fee revenue collected on a pair, counting both directions
+    pub fn get_fee_revenue_by_pair(env: Env, from: Symbol, to: Symbol) -> i128 {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        portfolio.get_user_tier(&env, user)
+        portfolio.get_fee_revenue_by_pair(&from, &to)
     }
 
-    // ===== RATE LIMITING =====
-
-    /// Get rate limit status for swap operations
-    pub fn get_swap_rate_limit(env: Env, user: Address) -> RateLimitStatus {
+    /// Get the number of trades executed platform-wide over the last `window_seconds`
+    pub fn get_global_trades_in_window(env: Env, window_seconds: u64) -> u32 {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        let user_tier = portfolio.get_user_tier(&env, user.clone());
-        RateLimiter::get_swap_status(&env, &user, &user_tier)
+        portfolio.get_global_trades_in_window(&env, window_seconds)
     }
 
-    /// Get rate limit status for LP operations
-    pub fn get_lp_rate_limit(env: Env, user: Address) -> RateLimitStatus {
+    /// Get the most recent `limit` pool utilization windows as (window start, utilization bps)
+    pub fn get_utilization_history(env: Env, limit: u32) -> Vec<(u64, u32)> {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        let user_tier = portfolio.get_user_tier(&env, user.clone());
-        RateLimiter::get_lp_status(&env, &user, &user_tier)
+        portfolio.get_utilization_history(&env, limit)
     }
 
-    // ===== BATCH OPERATIONS =====
+    /// Get cumulative swap input volume by direction: (XLM->USDCSIM, USDCSIM->XLM)
+    /// Comparing the two shows net sell vs buy pressure on XLM
+    pub fn get_directional_volume(env: Env) -> (i128, i128) {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-    pub fn execute_batch_atomic(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
-        let mut portfolio: Portfolio = env
+        portfolio.get_directional_volume()
+    }
+
+    /// Get a user's cumulative swap input volume by direction: (XLM->USDCSIM, USDCSIM->XLM)
+    pub fn get_user_direction_ratio(env: Env, user: Address) -> (i128, i128) {
+        let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        let result = execute_batch_atomic(&env, &mut portfolio, operations);
+        portfolio.get_user_direction_ratio(user)
+    }
 
-        match result {
-            Ok(res) => {
-                env.storage().instance().set(&(), &portfolio);
-                res
-            }
-            Err(_) => {
-                let mut err = BatchResult::new(&env);
-                err.operations_failed = 1;
-                err
-            }
-        }
+    /// Check if a user has earned a specific badge
+    pub fn has_badge(env: Env, user: Address, badge: Badge) -> bool {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(Portfolio::new);
+
+        portfolio.has_badge(&env, user, badge)
     }
 
-    pub fn execute_batch_best_effort(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
+    /// Admin: set the trade count needed for a trade-count badge (Trader, VeteranTrader, MasterTrader).
+    /// Requires migration first, so existing traders have been backfilled with the newer badges.
+    pub fn set_badge_threshold(env: Env, badge: Badge, threshold: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+        migration::require_migrated(&env)?;
+
         let mut portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        let result = execute_batch_best_effort(&env, &mut portfolio, operations);
-
-        match result {
-            Ok(res) => {
-                env.storage().instance().set(&(), &portfolio);
-                res
-            }
-            Err(_) => {
-                let mut err = BatchResult::new(&env);
-                err.operations_failed = 1;
-                err
-            }
-        }
+        portfolio.set_badge_threshold(badge, threshold);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
     }
 
-    pub fn execute_batch(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
-        Self::execute_batch_atomic(env, operations)
+    /// Get the trade count needed for a trade-count badge (0 for other badges)
+    pub fn get_badge_threshold(env: Env, badge: Badge) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_badge_threshold(badge)
     }
 
-    // ===== LIQUIDITY PROVIDER (LP) FUNCTIONS =====
+    /// Admin: set the swap fee discount (bps) for holders of `badge`; 0 removes it.
+    /// A user gets their single best badge discount off their tier fee.
+    pub fn set_badge_fee_discount(env: Env, badge: Badge, bps: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
 
-    /// Add liquidity to the pool and mint LP tokens
-    /// Returns the number of LP tokens minted
-    pub fn add_liquidity(env: Env, xlm_amount: i128, usdc_amount: i128, user: Address) -> i128 {
-        assert!(xlm_amount > 0, "XLM amount must be positive");
-        assert!(usdc_amount > 0, "USDC amount must be positive");
+        if bps > 10000 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_badge_fee_discount(badge, bps);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the swap fee discount (bps) granted by a badge
+    pub fn get_badge_fee_discount(env: Env, badge: Badge) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_badge_fee_discount(badge)
+    }
+
+    /// Admin: rebuild a stale leaderboard by re-ranking the given candidates on their current PnL
+    pub fn rebuild_leaderboard_from(env: Env, users: Vec<Address>) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.rebuild_leaderboard_from(&env, users);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Admin: award EarlyAdopter to the first `cutoff` unique traders (0 disables it),
+    /// including existing traders within the cutoff
+    pub fn set_early_adopter_cutoff(env: Env, cutoff: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_early_adopter_cutoff(&env, cutoff);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get how many of the first unique traders earn EarlyAdopter
+    pub fn get_early_adopter_cutoff(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_early_adopter_cutoff()
+    }
+
+    /// Admin: set the chance (bps) that `maybe_award_bonus` grants the LuckyDraw badge
+    pub fn set_bonus_probability_bps(env: Env, bps: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        prng::set_bonus_probability_bps(&env, bps);
+        Ok(())
+    }
+
+    /// Draw for the LuckyDraw badge. The draw is deterministic per ledger and user, so
+    /// retrying within the same ledger gives the same result. Returns true if awarded.
+    pub fn maybe_award_bonus(env: Env, user: Address) -> Result<bool, SwapTradeError> {
+        user.require_auth();
+
+        if is_blacklisted(&env, &user) {
+            return Err(SwapTradeError::Blacklisted);
+        }
+
+        let bps = prng::get_bonus_probability_bps(&env);
+        if !prng::roll_bps(&env, &user, 0, bps) {
+            return Ok(false);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let awarded = portfolio.award_badge(&env, user, Badge::LuckyDraw);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(awarded)
+    }
+
+    /// Get badges for several users at once, in input order
+    pub fn get_badges_for_users(env: Env, users: Vec<Address>) -> Vec<Vec<Badge>> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let mut result = Vec::new(&env);
+        for user in users.iter() {
+            result.push_back(portfolio.get_user_badges(&env, user));
+        }
+        result
+    }
+
+    /// Get the ledger timestamp at which a user earned a badge (None if not earned)
+    pub fn get_badge_award_time(env: Env, user: Address, badge: Badge) -> Option<u64> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_badge_award_time(user, badge)
+    }
+
+    /// Admin: defer badge checks out of swaps and LP deposits; badges are then awarded via `sync_badges`
+    pub fn set_defer_badge_checks(env: Env, deferred: bool) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_defer_badge_checks(deferred);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get whether badge checks are deferred to `sync_badges`
+    pub fn get_defer_badge_checks(env: Env) -> bool {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.badge_checks_deferred()
+    }
+
+    /// Evaluate and award every badge the user qualifies for. Callable by anyone,
+    /// e.g. a keeper, when inline badge checks are deferred. Returns the user's badges.
+    pub fn sync_badges(env: Env, user: Address) -> Vec<Badge> {
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.check_and_award_badges(&env, user.clone());
+        env.storage().instance().set(&(), &portfolio);
+        portfolio.get_user_badges(&env, user)
+    }
+
+    /// Get all badges earned by a user
+    pub fn get_user_badges(env: Env, user: Address) -> Vec<Badge> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(Portfolio::new);
+
+        portfolio.get_user_badges(&env, user)
+    }
+
+    /// Get how many badges a user has earned
+    pub fn get_user_badge_count(env: Env, user: Address) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_user_badge_count(user)
+    }
+
+    pub fn get_user_transactions(env: Env, user: Address, limit: u32) -> Vec<Transaction> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_user_transactions(&env, user, limit)
+    }
+
+    /// Get up to `limit` of a user's most recent swaps rejected by `try_swap`, oldest first
+    pub fn get_user_failed_swaps(env: Env, user: Address, limit: u32) -> Vec<FailedSwap> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_user_failed_swaps(&env, user, limit)
+    }
+
+    /// Count a user's trades in the last `window_seconds`, from their retained transaction history
+    pub fn get_user_trades_in_window(env: Env, user: Address, window_seconds: u64) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_user_trades_in_window(&env, user, window_seconds)
+    }
+
+    /// Get the ledger timestamp of the first mint or swap, if any has happened
+    pub fn get_first_activity_time(env: Env) -> Option<u64> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_first_activity_time()
+    }
+
+    /// Get seconds elapsed since the first mint or swap (0 before any activity)
+    pub fn get_activity_age(env: Env) -> u64 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio
+            .get_first_activity_time()
+            .map_or(0, |first| env.ledger().timestamp().saturating_sub(first))
+    }
+
+    /// Get the largest swap by input amount and the user who made it
+    pub fn get_largest_trade(env: Env) -> Option<(Address, i128)> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_largest_trade()
+    }
+
+    /// Get the reserves, fee and output of a user's most recent swap, if any
+    pub fn get_last_swap_details(env: Env, user: Address) -> Option<SwapDetails> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_last_swap_details(user)
+    }
+
+    /// Get a user's trades, LP actions and badge awards as one chronological feed
+    /// Returns up to `limit` of the most recent events, oldest first
+    pub fn get_activity_timeline(env: Env, user: Address, limit: u32) -> Vec<ActivityEvent> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_activity_timeline(&env, user, limit)
+    }
+
+    /// Admin: set how many transactions are retained per user (oldest evicted first)
+    pub fn set_tx_history_cap(env: Env, cap: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_tx_history_cap(cap);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Admin: set the most transactions `get_user_transactions` returns, whatever the requested limit
+    pub fn set_max_tx_return(env: Env, max: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_max_tx_return(max);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the most transactions `get_user_transactions` returns (100 by default)
+    pub fn get_max_tx_return(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_max_tx_return()
+    }
+
+    /// Admin: set the decimals recorded in `rate_achieved` for a pair (capped at 18)
+    pub fn set_rate_decimals(env: Env, a: Symbol, b: Symbol, decimals: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_rate_decimals(&a, &b, decimals);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the decimals recorded in `rate_achieved` for a pair
+    pub fn get_rate_decimals(env: Env, a: Symbol, b: Symbol) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_rate_decimals(&a, &b)
+    }
+
+    /// Admin: return the total trading volume, reset it to zero and start a new volume epoch
+    pub fn archive_and_reset_volume(env: Env) -> Result<i128, SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let archived = portfolio.archive_and_reset_volume();
+        env.storage().instance().set(&(), &portfolio);
+        Ok(archived)
+    }
+
+    /// Admin: stop swaps once gross swap volume would pass `cap`, e.g. for a capped beta (0 removes the cap).
+    /// Volume counts toward the cap from the first swap and is not reset by `archive_and_reset_volume`.
+    pub fn set_global_volume_cap(env: Env, cap: i128) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        if cap < 0 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_global_volume_cap(cap);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
+
+    /// Get the global trading volume cap (0 when uncapped)
+    pub fn get_global_volume_cap(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_global_volume_cap()
+    }
+
+    /// Get the gross swap volume counted against the global volume cap
+    pub fn get_capped_volume(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_capped_volume()
+    }
+
+    /// Get the number of times the total trading volume has been archived
+    pub fn get_volume_epoch(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_volume_epoch()
+    }
+
+    /// Get the number of unique users who traded during the current UTC day
+    pub fn get_daily_active_users(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_daily_active_users(&env)
+    }
+
+    /// Get a user's net worth in USDCSIM across all assets plus their LP position
+    /// XLM is valued at the current pool rate, other registered assets via their
+    /// oracle price against USDCSIM (skipped if no price is set or it is past the grace window)
+    pub fn get_net_worth(env: Env, user: Address) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let usdc = portfolio.stable_symbol();
+        let xlm_balance = portfolio.balance_of(&env, Asset::XLM, user.clone());
+        let mut total = portfolio.xlm_value_in_usdc(xlm_balance);
+        total = total.saturating_add(portfolio.balance_of(&env, Asset::Custom(usdc.clone()), user.clone()));
+
+        for symbol in assets::registered_assets(&env).iter() {
+            if symbol == symbol_short!("XLM") || symbol == usdc {
+                continue;
+            }
+            let balance = portfolio.balance_of(&env, Asset::Custom(symbol.clone()), user.clone());
+            if balance <= 0 {
+                continue;
+            }
+            if let Ok(quote) = get_price_safe(&env, (symbol, usdc.clone())) {
+                let value = (balance as u128).saturating_mul(quote.price) / 1_000_000_000_000_000_000u128;
+                total = total.saturating_add(value as i128);
+            }
+        }
+
+        total.saturating_add(portfolio.lp_position_value_in_usdc(user))
+    }
+
+    /// Get the current tier for a user
+    pub fn get_user_tier(env: Env, user: Address) -> UserTier {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_user_tier(&env, user)
+    }
+
+    // ===== RATE LIMITING =====
+
+    /// Admin: set how many fee-free swaps users of `tier` get each UTC day (0 disables them)
+    pub fn set_free_swap_allowance(env: Env, tier: UserTier, swaps_per_day: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        trading::set_free_swap_allowance(&env, &tier, swaps_per_day);
+        Ok(())
+    }
+
+    /// Get the daily fee-free swap allowance for `tier`
+    pub fn get_free_swap_allowance(env: Env, tier: UserTier) -> u32 {
+        trading::get_free_swap_allowance(&env, &tier)
+    }
+
+    /// Get how many free swaps `user` has left today
+    pub fn get_free_swaps_remaining(env: Env, user: Address) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let tier = portfolio.get_user_tier(&env, user.clone());
+        trading::get_free_swap_allowance(&env, &tier).saturating_sub(trading::free_swaps_used_today(&env, &user))
+    }
+
+    /// Get rate limit status for swap operations
+    pub fn get_swap_rate_limit(env: Env, user: Address) -> RateLimitStatus {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let user_tier = portfolio.get_user_tier(&env, user.clone());
+        RateLimiter::get_swap_status(&env, &user, &user_tier)
+    }
+
+    /// Get swap rate limit statuses for many users in one call
+    /// Results are returned in the same order as `users`
+    pub fn get_swap_rate_limits(env: Env, users: Vec<Address>) -> Vec<RateLimitStatus> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let mut statuses = Vec::new(&env);
+        for user in users.iter() {
+            let user_tier = portfolio.get_user_tier(&env, user.clone());
+            statuses.push_back(RateLimiter::get_swap_status(&env, &user, &user_tier));
+        }
+        statuses
+    }
+
+    /// Get rate limit status for LP operations
+    pub fn get_lp_rate_limit(env: Env, user: Address) -> RateLimitStatus {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let user_tier = portfolio.get_user_tier(&env, user.clone());
+        RateLimiter::get_lp_status(&env, &user, &user_tier)
+    }
+
+    /// Admin: switch rate limiting between fixed and sliding windows; usage so far carries over
+    pub fn set_rate_limit_mode(env: Env, mode: RateLimitMode) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        RateLimiter::set_mode(&env, mode);
+        Ok(())
+    }
+
+    /// Get the active rate limit window mode
+    pub fn get_rate_limit_mode(env: Env) -> RateLimitMode {
+        RateLimiter::get_mode(&env)
+    }
+
+    /// Admin: limit each user's swaps on a pair (either direction) per hour, on top of
+    /// their tier limit. 0 removes the pair limit.
+    pub fn set_pair_swap_limit(env: Env, from: Symbol, to: Symbol, swaps_per_hour: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        RateLimiter::set_pair_swap_limit(&env, &from, &to, swaps_per_hour);
+        Ok(())
+    }
+
+    /// Get the per-user hourly swap limit for a pair, if one is configured
+    pub fn get_pair_swap_limit(env: Env, from: Symbol, to: Symbol) -> Option<u32> {
+        RateLimiter::get_pair_swap_limit(&env, &from, &to)
+    }
+
+    // ===== ADMIN CONTROLS =====
+
+    /// Admin: block a user from swapping, minting and LP operations
+    pub fn blacklist_user(env: Env, user: Address) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        admin::set_blacklisted(&env, &user, true);
+        Ok(())
+    }
+
+    /// Admin: restore a blacklisted user's access
+    pub fn unblacklist_user(env: Env, user: Address) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        admin::set_blacklisted(&env, &user, false);
+        Ok(())
+    }
+
+    /// Check whether a user is blacklisted
+    pub fn is_blacklisted(env: Env, user: Address) -> bool {
+        is_blacklisted(&env, &user)
+    }
+
+    /// Admin: reject new LP deposits; swaps and withdrawals keep working
+    pub fn freeze_deposits(env: Env) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        admin::set_deposits_frozen(&env, true);
+        Ok(())
+    }
+
+    /// Admin: accept LP deposits again
+    pub fn unfreeze_deposits(env: Env) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        admin::set_deposits_frozen(&env, false);
+        Ok(())
+    }
+
+    /// Check whether new LP deposits are frozen
+    pub fn is_deposits_frozen(env: Env) -> bool {
+        is_deposits_frozen(&env)
+    }
+
+    /// Get the current admin, or None if no admin has been set
+    pub fn get_admin(env: Env) -> Option<Address> {
+        admin::get_admin(&env)
+    }
+
+    // ===== ORACLE =====
+
+    /// Admin: set how many seconds past the staleness threshold a price is still served, flagged stale
+    pub fn set_price_grace_window(env: Env, seconds: u64) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        oracle::set_grace_window(&env, seconds);
+        Ok(())
+    }
+
+    /// Get the stale-price grace window in seconds (0 = no fallback)
+    pub fn get_price_grace_window(env: Env) -> u64 {
+        oracle::get_grace_window(&env)
+    }
+
+    /// Admin: set the maximum age (seconds) of an oracle price used to price swaps,
+    /// independent of the staleness threshold for general price reads
+    pub fn set_swap_oracle_max_age(env: Env, seconds: u64) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        trading::set_swap_oracle_max_age(&env, seconds);
+        Ok(())
+    }
+
+    /// Get the maximum oracle price age for swaps (the general staleness threshold by default)
+    pub fn get_swap_oracle_max_age(env: Env) -> u64 {
+        trading::get_swap_oracle_max_age(&env)
+    }
+
+    /// Admin: choose how swaps are priced (pool reserves, oracle, or the better of the two)
+    pub fn set_pricing_mode(env: Env, mode: PricingMode) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        trading::set_pricing_mode(&env, mode);
+        Ok(())
+    }
+
+    /// Get the current swap pricing mode
+    pub fn get_pricing_mode(env: Env) -> PricingMode {
+        trading::get_pricing_mode(&env)
+    }
+
+    /// Admin: set the fee rebate (bps) for swaps that move the pool toward its target ratio
+    pub fn set_rebalance_rebate(env: Env, bps: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        if bps > 10000 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+        trading::set_rebalance_rebate_bps(&env, bps);
+        Ok(())
+    }
+
+    /// Get the rebalancing fee rebate in bps (0 by default)
+    pub fn get_rebalance_rebate(env: Env) -> u32 {
+        trading::get_rebalance_rebate_bps(&env)
+    }
+
+    /// Get the XLM/stable-asset oracle price and its confidence in bps ((0, 0) if no price is set).
+    /// A price stored as stable/XLM is inverted.
+    pub fn get_price_with_confidence(env: Env) -> (u128, u32) {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        trading::price_with_confidence(&env, &symbol_short!("XLM"), &portfolio.stable_symbol())
+            .unwrap_or((0, 0))
+    }
+
+    // ===== QUOTES =====
+
+    /// Estimate the round-trip spread in bps for `amount` USDCSIM:
+    /// the XLM cost to buy `amount` versus the XLM received selling it back (fees + price impact).
+    /// Returns 0 if the pool has no liquidity.
+    pub fn get_spread_bps(env: Env, amount: i128) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        round_trip_spread_bps(&portfolio, amount)
+    }
+
+    /// Preview a swap for `user`: output, fee, price impact and the minimum received at
+    /// `slippage_bps`. Uses the same fee and pricing logic as `swap`; nothing is executed.
+    /// A non-positive amount, unsupported pair or empty pool sets `failure` instead.
+    pub fn preview_swap(env: Env, user: Address, from: Symbol, to: Symbol, amount: i128, slippage_bps: u32) -> SwapPreview {
+        assert!(slippage_bps <= 10000, "Slippage must be <= 10000 bps");
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let failure = if amount <= 0 {
+            Some(OrderFailure::ZeroAmount)
+        } else if !trading::is_supported_pair(&portfolio, &from, &to) {
+            Some(OrderFailure::InvalidPair)
+        } else if !has_liquidity(&portfolio) {
+            Some(OrderFailure::NoLiquidity)
+        } else {
+            None
+        };
+        if failure.is_some() {
+            return SwapPreview { expected_out: 0, fee_amount: 0, price_impact_bps: 0, min_received: 0, failure };
+        }
+
+        let fee_bps = trading::effective_swap_fee_bps(&env, &portfolio, user, &from, &to, amount);
+        let fee_amount = trading::fee_round_up(amount, fee_bps);
+        let swap_amount = amount - fee_amount;
+
+        let expected_out = trading::quote_swap_out(&env, &portfolio, &from, &to, swap_amount);
+        let spot = spot_quote(&portfolio, &from, swap_amount);
+        let price_impact_bps = if spot > expected_out {
+            ((spot - expected_out) * 10000 / spot) as u32
+        } else {
+            0
+        };
+        let min_received = expected_out * (10000 - slippage_bps as i128) / 10000;
+
+        SwapPreview { expected_out, fee_amount, price_impact_bps, min_received, failure: None }
+    }
+
+    /// Get the output `user` would receive for swapping `amount` of `from` into `to`, after
+    /// their tier fee and the pool's price impact. View-only; needs no auth.
+    /// Returns 0 for an unsupported pair or a non-positive amount.
+    pub fn get_amount_out(env: Env, user: Address, from: Symbol, to: Symbol, amount: i128) -> i128 {
+        if amount <= 0 {
+            return 0;
+        }
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+        if !trading::is_supported_pair(&portfolio, &from, &to) {
+            return 0;
+        }
+
+        Self::preview_swap(env, user, from, to, amount, 0).expected_out
+    }
+
+    /// Get the all-in rate a user would receive for swapping `amount`: output per unit of
+    /// input after fees and price impact, scaled by 1e7. Returns 0 for an unsupported pair or
+    /// if nothing would come out.
+    pub fn get_effective_price(env: Env, user: Address, from: Symbol, to: Symbol, amount: i128) -> u128 {
+        if amount <= 0 {
+            return 0;
+        }
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+        if !trading::is_supported_pair(&portfolio, &from, &to) {
+            return 0;
+        }
+        let expected_out = Self::preview_swap(env, user, from, to, amount, 0).expected_out;
+        if expected_out <= 0 {
+            return 0;
+        }
+        (expected_out as u128).saturating_mul(RATE_PRECISION) / (amount as u128)
+    }
+
+    /// Quote `user` swapping `amount` along `path` (e.g. [XLM, USDCSIM, XLM]) at their own fees,
+    /// chaining each hop through the pool without changing state
+    pub fn quote_route(env: Env, user: Address, path: Vec<Symbol>, amount: i128) -> Result<i128, SwapTradeError> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        trading::quote_route(&env, &portfolio, &path, amount, user)
+    }
+
+    /// Input needed to move the pool's spot rate (USDCSIM per XLM, scaled by 1e7) to `target_rate`,
+    /// including the LP fee. Denominated in XLM when the target is below the current rate and in
+    /// USDCSIM when above. Returns 0 if the pool is empty or already at the target.
+    pub fn get_depth_to_price(env: Env, target_rate: i128) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        depth_to_rate(&portfolio, target_rate)
+    }
+
+    /// Get each asset's share of pool value in bps as (XLM, stable), summing to 10000.
+    /// XLM is valued at the oracle price when one is available, otherwise at the pool rate
+    /// (which by construction splits the value evenly). Returns (0, 0) for an empty pool.
+    pub fn get_pool_composition_bps(env: Env) -> (u32, u32) {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        if !has_liquidity(&portfolio) {
+            return (0, 0);
+        }
+        let xlm_reserve = portfolio.get_liquidity(Asset::XLM);
+        let stable_reserve = portfolio.get_liquidity(portfolio.stable_asset());
+
+        let xlm_value = match get_price_safe(&env, (symbol_short!("XLM"), portfolio.stable_symbol())) {
+            Ok(quote) => ((xlm_reserve as u128).saturating_mul(quote.price) / 1_000_000_000_000_000_000u128) as i128,
+            Err(_) => portfolio.xlm_value_in_usdc(xlm_reserve),
+        };
+        let total = xlm_value.saturating_add(stable_reserve);
+        if total <= 0 {
+            return (0, 0);
+        }
+
+        let xlm_bps = (xlm_value.saturating_mul(10000) / total) as u32;
+        (xlm_bps, 10000 - xlm_bps)
+    }
+
+    /// Get the total value locked in the pool in USDCSIM, XLM valued at the current pool rate
+    pub fn get_tvl_usdc(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_tvl_usdc()
+    }
+
+    /// Get the reserves of the pool trading `from` against `to`, in (from, to) order.
+    /// Only the XLM/stable pool exists; any other pair returns (0, 0).
+    pub fn get_pair_reserves(env: Env, from: Symbol, to: Symbol) -> (i128, i128) {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        trading::pair_reserves(&portfolio, &from, &to)
+    }
+
+    // ===== ASSET METADATA =====
+
+    /// Admin: register metadata (decimals, name, transfer fee) for an asset symbol
+    pub fn set_asset_metadata(
+        env: Env,
+        symbol: Symbol,
+        decimals: u32,
+        display_name: String,
+        transfer_fee_bps: u32,
+    ) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+        assert!(transfer_fee_bps <= 10000, "Transfer fee must be <= 10000 bps");
+        if assets::would_exceed_limit(&env, &symbol) {
+            return Err(SwapTradeError::TooManyAssets);
+        }
+
+        assets::set_metadata(
+            &env,
+            symbol.clone(),
+            &AssetMetadata { decimals, display_name, transfer_fee_bps },
+        );
+        assets::register(&env, symbol);
+        Ok(())
+    }
+
+    /// Get display metadata for an asset, or None if it was never registered
+    pub fn get_asset_metadata(env: Env, symbol: Symbol) -> Option<AssetMetadata> {
+        assets::get_metadata(&env, symbol)
+    }
+
+    /// Admin: cap the number of distinct registered assets (0 = unlimited)
+    pub fn set_max_assets(env: Env, max_assets: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        assets::set_max_assets(&env, max_assets);
+        Ok(())
+    }
+
+    /// Get the registered-asset limit (0 = unlimited)
+    pub fn get_max_assets(env: Env) -> u32 {
+        assets::get_max_assets(&env)
+    }
+
+    /// Get the number of registered assets
+    pub fn get_registered_asset_count(env: Env) -> u32 {
+        assets::registered_assets(&env).len()
+    }
+
+    /// Admin: cap the pool reserve of an asset (0 = uncapped)
+    pub fn set_reserve_cap(env: Env, asset: Symbol, cap: i128) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+        assert!(cap >= 0, "Reserve cap must be non-negative");
+
+        assets::set_reserve_cap(&env, asset, cap);
+        Ok(())
+    }
+
+    /// Get the pool reserve cap for an asset (0 = uncapped)
+    pub fn get_reserve_cap(env: Env, asset: Symbol) -> i128 {
+        assets::get_reserve_cap(&env, asset)
+    }
+
+    // ===== BATCH OPERATIONS =====
+
+    pub fn execute_batch_atomic(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let result = execute_batch_atomic(&env, &mut portfolio, operations);
+
+        match result {
+            Ok(res) => {
+                env.storage().instance().set(&(), &portfolio);
+                res
+            }
+            Err(_) => {
+                let mut err = BatchResult::new(&env);
+                err.operations_failed = 1;
+                err
+            }
+        }
+    }
+
+    pub fn execute_batch_best_effort(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let result = execute_batch_best_effort(&env, &mut portfolio, operations);
+
+        match result {
+            Ok(res) => {
+                env.storage().instance().set(&(), &portfolio);
+                res
+            }
+            Err(_) => {
+                let mut err = BatchResult::new(&env);
+                err.operations_failed = 1;
+                err
+            }
+        }
+    }
+
+    pub fn execute_batch(env: Env, operations: Vec<BatchOperation>) -> BatchResult {
+        Self::execute_batch_atomic(env, operations)
+    }
+
+    // ===== LIQUIDITY PROVIDER (LP) FUNCTIONS =====
+
+    /// Add liquidity to the pool and mint LP tokens
+    /// Returns the number of LP tokens minted
+    /// Rejected with `TradingPaused` while trading is paused
+    pub fn add_liquidity(env: Env, xlm_amount: i128, usdc_amount: i128, user: Address) -> Result<i128, SwapTradeError> {
+        assert!(xlm_amount > 0, "XLM amount must be positive");
+        assert!(usdc_amount > 0, "USDC amount must be positive");
+
+        if is_paused(&env) {
+            return Err(SwapTradeError::TradingPaused);
+        }
+        if is_deposits_frozen(&env) {
+            return Err(SwapTradeError::DepositsFrozen);
+        }
+        if is_blacklisted(&env, &user) {
+            return Err(SwapTradeError::Blacklisted);
+        }
 
         let mut portfolio: Portfolio = env
             .storage()
@@ -402,109 +1713,506 @@ impl CounterContract {
             panic!("RATELIMIT");
         }
 
-        // Get current pool state
-        let current_xlm = portfolio.get_liquidity(Asset::XLM);
-        let current_usdc = portfolio.get_liquidity(Asset::Custom(symbol_short!("USDCSIM")));
-        let total_lp_tokens = portfolio.get_total_lp_tokens();
+        if trading::deposit_value(&portfolio, xlm_amount, usdc_amount) < portfolio.get_min_lp_deposit() {
+            return Err(SwapTradeError::DepositTooSmall);
+        }
+        if !portfolio.can_open_lp_position(user.clone()) {
+            return Err(SwapTradeError::TooManyPositions);
+        }
+
+        // Get current pool state
+        let stable = portfolio.stable_symbol();
+        let current_xlm = portfolio.get_liquidity(Asset::XLM);
+        let current_usdc = portfolio.get_liquidity(portfolio.stable_asset());
+        let total_lp_tokens = portfolio.get_total_lp_tokens();
+
+        // Check user has sufficient balance
+        let user_xlm_balance = portfolio.balance_of(&env, Asset::XLM, user.clone());
+        let user_usdc_balance = portfolio.balance_of(&env, portfolio.stable_asset(), user.clone());
+        
+        assert!(user_xlm_balance >= xlm_amount, "Insufficient XLM balance");
+        assert!(user_usdc_balance >= usdc_amount, "Insufficient USDC balance");
+
+        // Fee-on-transfer assets deliver less than was sent; the pool only counts what it receives
+        let xlm_received = assets::net_received(&env, &symbol_short!("XLM"), xlm_amount);
+        let usdc_received = assets::net_received(&env, &stable, usdc_amount);
+
+        if assets::exceeds_reserve_cap(&env, symbol_short!("XLM"), current_xlm.saturating_add(xlm_received))
+            || assets::exceeds_reserve_cap(&env, stable.clone(), current_usdc.saturating_add(usdc_received))
+        {
+            return Err(SwapTradeError::ReserveCapExceeded);
+        }
+
+        // Calculate LP tokens to mint using constant product AMM formula
+        // If pool is empty, LP tokens = sqrt(xlm * usdc)
+        // Otherwise, LP tokens = (deposit / pool_size) * total_lp_tokens
+        let lp_tokens_minted = if total_lp_tokens == 0 {
+            // First liquidity provider: LP tokens = sqrt(xlm * usdc)
+            // Use integer square root (Babylonian method)
+            let product = (xlm_received as u128).saturating_mul(usdc_received as u128);
+            if product == 0 {
+                panic!("Product must be positive");
+            }
+            // Integer square root using Babylonian method
+            let mut guess = product;
+            let mut prev_guess = 0u128;
+            // Limit iterations to prevent infinite loop
+            let mut iterations = 0;
+            while guess != prev_guess && iterations < 100 {
+                prev_guess = guess;
+                let quotient = product / guess;
+                guess = (guess + quotient) / 2;
+                if guess == 0 {
+                    guess = 1;
+                    break;
+                }
+                iterations += 1;
+            }
+            trading::u128_to_i128(guess)?
+        } else {
+            // Calculate proportional share
+            // LP tokens = min((xlm_received / current_xlm) * total_lp_tokens, (usdc_received / current_usdc) * total_lp_tokens)
+            // This ensures the ratio is maintained
+            let xlm_share = if current_xlm > 0 {
+                (xlm_received as u128).saturating_mul(total_lp_tokens as u128) / (current_xlm as u128)
+            } else {
+                0
+            };
+            let usdc_share = if current_usdc > 0 {
+                (usdc_received as u128).saturating_mul(total_lp_tokens as u128) / (current_usdc as u128)
+            } else {
+                0
+            };
+            
+            // Take minimum to maintain ratio
+            trading::u128_to_i128(core::cmp::min(xlm_share, usdc_share))?
+        };
+
+        assert!(lp_tokens_minted > 0, "LP tokens minted must be positive");
+
+        // Debit assets from user (transfer to pool)
+        portfolio.debit(&env, Asset::XLM, user.clone(), xlm_amount);
+        portfolio.debit(&env, Asset::Custom(stable), user.clone(), usdc_amount);
+
+        // Entry price is the pool rate before this deposit; the first deposit sets the rate itself
+        let entry_rate = if has_liquidity(&portfolio) {
+            pool_rate(&portfolio)
+        } else {
+            (usdc_received as u128).saturating_mul(RATE_PRECISION) / (xlm_received as u128)
+        };
+
+        // Update pool liquidity
+        portfolio.add_pool_liquidity(xlm_received, usdc_received);
+
+        // Update or create LP position
+        let existing_position = portfolio.get_lp_position(user.clone());
+        let new_position = if let Some(mut pos) = existing_position {
+            // Update existing position, weighting the entry price by LP tokens
+            let old_tokens = pos.lp_tokens_minted.max(0) as u128;
+            let total_tokens = old_tokens.saturating_add(lp_tokens_minted as u128);
+            pos.entry_price = pos
+                .entry_price
+                .saturating_mul(old_tokens)
+                .saturating_add(entry_rate.saturating_mul(lp_tokens_minted as u128))
+                / total_tokens;
+            pos.xlm_deposited = pos.xlm_deposited.saturating_add(xlm_received);
+            pos.usdc_deposited = pos.usdc_deposited.saturating_add(usdc_received);
+            pos.lp_tokens_minted = pos.lp_tokens_minted.saturating_add(lp_tokens_minted);
+            pos
+        } else {
+            // Create new position
+            LPPosition {
+                lp_address: user.clone(),
+                xlm_deposited: xlm_received,
+                usdc_deposited: usdc_received,
+                lp_tokens_minted,
+                entry_price: entry_rate,
+            }
+        };
+
+        portfolio.set_lp_position(user.clone(), new_position);
+        portfolio.add_total_lp_tokens(lp_tokens_minted);
+
+        // Record LP deposit for badge tracking, fee eligibility and the activity timeline
+        portfolio.record_lp_deposit(user.clone());
+        portfolio.record_lp_deposit_history(&env, user.clone(), LPDepositRecord {
+            timestamp: env.ledger().timestamp(),
+            xlm_amount: xlm_received,
+            usdc_amount: usdc_received,
+            lp_tokens_minted,
+        });
+        portfolio.record_lp_deposit_time(&env, user.clone());
+        portfolio.record_activity(
+            &env,
+            user.clone(),
+            ActivityEvent::LiquidityAdded(env.ledger().timestamp(), xlm_received, usdc_received),
+        );
+        if !portfolio.badge_checks_deferred() {
+            portfolio.check_and_award_badges(&env, user.clone());
+        }
+
+        // Record rate limit usage
+        RateLimiter::record_lp_op(&env, &user, env.ledger().timestamp());
+
+        env.storage().instance().set(&(), &portfolio);
+
+        // Optional structured logging for the deposit
+        #[cfg(feature = "logging")]
+        {
+            env.events().publish(
+                (Symbol::new(&env, "lp_added"),),
+                (user, xlm_received, usdc_received, lp_tokens_minted),
+            );
+        }
+
+        Ok(lp_tokens_minted)
+    }
+
+    /// Remove liquidity from the pool by burning LP tokens
+    /// Returns (xlm_amount, usdc_amount) returned to user
+    /// Removals stay allowed while trading is paused so LPs can always exit
+    pub fn remove_liquidity(env: Env, lp_tokens: i128, user: Address) -> Result<(i128, i128), SwapTradeError> {
+        assert!(lp_tokens > 0, "LP tokens must be positive");
+
+        if is_blacklisted(&env, &user) {
+            return Err(SwapTradeError::Blacklisted);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        // Get user's LP position
+        let position = portfolio.get_lp_position(user.clone());
+        assert!(position.is_some(), "User has no LP position");
+        let mut pos = position.unwrap();
+
+        // Verify user has enough LP tokens
+        assert!(pos.lp_tokens_minted >= lp_tokens, "Insufficient LP tokens");
+
+        // Get current pool state
+        let current_xlm = portfolio.get_liquidity(Asset::XLM);
+        let current_usdc = portfolio.get_liquidity(portfolio.stable_asset());
+        let total_lp_tokens = portfolio.get_total_lp_tokens();
+
+        // A position with no LP tokens outstanding means the accounting is corrupt;
+        // an admin can repair it with `reset_lp_accounting`
+        if total_lp_tokens <= 0 {
+            return Err(SwapTradeError::NoLiquidity);
+        }
+
+        // Calculate proportional share of pool
+        // xlm_amount = (lp_tokens / total_lp_tokens) * current_xlm
+        // usdc_amount = (lp_tokens / total_lp_tokens) * current_usdc
+        let xlm_amount = trading::u128_to_i128((lp_tokens as u128).saturating_mul(current_xlm as u128) / (total_lp_tokens as u128))?;
+        let usdc_amount = trading::u128_to_i128((lp_tokens as u128).saturating_mul(current_usdc as u128) / (total_lp_tokens as u128))?;
+
+        assert!(xlm_amount > 0 && usdc_amount > 0, "Amounts must be positive");
+
+        // Verify we're not removing more than deposited (with rounding tolerance)
+        // Allow small rounding differences
+        let max_xlm = pos.xlm_deposited;
+        let max_usdc = pos.usdc_deposited;
+        
+        // Check if removing more than deposited (with 1% tolerance for rounding)
+        if xlm_amount > max_xlm.saturating_mul(101) / 100 || usdc_amount > max_usdc.saturating_mul(101) / 100 {
+            panic!("Cannot remove more than deposited");
+        }
+
+        // The exit fee stays in the pool for the remaining LPs
+        let exit_fee_bps = portfolio.get_lp_exit_fee_bps();
+        let xlm_out = xlm_amount - trading::fee_round_up(xlm_amount, exit_fee_bps);
+        let usdc_out = usdc_amount - trading::fee_round_up(usdc_amount, exit_fee_bps);
+
+        // Update pool liquidity (subtract)
+        portfolio.set_liquidity(Asset::XLM, current_xlm.saturating_sub(xlm_out));
+        portfolio.set_liquidity(portfolio.stable_asset(), current_usdc.saturating_sub(usdc_out));
+
+        // Transfer assets from pool to user
+        portfolio.payout(&env, Asset::XLM, user.clone(), xlm_out);
+        portfolio.payout(&env, portfolio.stable_asset(), user.clone(), usdc_out);
+
+        // Update LP position
+        pos.lp_tokens_minted = pos.lp_tokens_minted.saturating_sub(lp_tokens);
+        pos.xlm_deposited = pos.xlm_deposited.saturating_sub(xlm_amount);
+        pos.usdc_deposited = pos.usdc_deposited.saturating_sub(usdc_amount);
+
+        if pos.lp_tokens_minted == 0 {
+            // Fully withdrawn: clear leftover deposit figures so no residual position remains
+            pos.xlm_deposited = 0;
+            pos.usdc_deposited = 0;
+            pos.entry_price = 0;
+        }
+        portfolio.set_lp_position(user.clone(), pos);
+        portfolio.subtract_total_lp_tokens(lp_tokens);
+        portfolio.record_activity(
+            &env,
+            user.clone(),
+            ActivityEvent::LiquidityRemoved(env.ledger().timestamp(), xlm_out, usdc_out),
+        );
+
+        // Record rate limit usage
+        RateLimiter::record_lp_op(&env, &user, env.ledger().timestamp());
+
+        env.storage().instance().set(&(), &portfolio);
+
+        // Optional structured logging for the withdrawal
+        #[cfg(feature = "logging")]
+        {
+            env.events().publish(
+                (Symbol::new(&env, "lp_removed"),),
+                (user, xlm_out, usdc_out, lp_tokens),
+            );
+        }
+
+        Ok((xlm_out, usdc_out))
+    }
+
+    /// Withdraw a user's entire LP position, burning all their LP tokens.
+    /// Returns (xlm_amount, usdc_amount) like `remove_liquidity`.
+    pub fn remove_all_liquidity(env: Env, user: Address) -> Result<(i128, i128), SwapTradeError> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let lp_tokens = portfolio
+            .get_lp_position(user.clone())
+            .map(|position| position.lp_tokens_minted)
+            .unwrap_or(0);
+        if lp_tokens <= 0 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+
+        Self::remove_liquidity(env, lp_tokens, user)
+    }
+
+    /// Admin: reinvest collected protocol fees into the pool reserves at the current reserve
+    /// ratio, minting protocol-owned LP tokens; fees that don't fit the ratio stay held.
+    /// Returns the LP tokens minted.
+    pub fn compound_protocol_fees(env: Env) -> Result<i128, SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let minted = portfolio.compound_protocol_fees();
+        env.storage().instance().set(&(), &portfolio);
+        Ok(minted)
+    }
+
+    /// Admin: add protocol-owned liquidity to the pool, paid for from the admin's own balances.
+    /// Returns the LP tokens minted to the protocol.
+    pub fn seed_protocol_liquidity(env: Env, xlm_amount: i128, usdc_amount: i128) -> Result<i128, SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+        if xlm_amount <= 0 || usdc_amount <= 0 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        if portfolio.balance_of(&env, Asset::XLM, caller.clone()) < xlm_amount
+            || portfolio.balance_of(&env, portfolio.stable_asset(), caller.clone()) < usdc_amount
+        {
+            return Err(SwapTradeError::InsufficientBalance);
+        }
+
+        let minted = portfolio.seed_protocol_liquidity(&env, caller, xlm_amount, usdc_amount);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(minted)
+    }
+
+    /// Get the USDCSIM value of all protocol fees compounded into the pool so far
+    pub fn get_protocol_fees_compounded(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_protocol_fees_compounded()
+    }
+
+    /// Get the protocol's LP profit or loss in USDCSIM: its share of the pool now less what it put in
+    pub fn get_protocol_lp_pnl(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_protocol_lp_pnl()
+    }
+
+    /// Admin: recompute total LP tokens from the individual positions after inconsistent accounting
+    pub fn reset_lp_accounting(env: Env) -> Result<i128, SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let total = portfolio.recompute_total_lp_tokens();
+        env.storage().instance().set(&(), &portfolio);
+        Ok(total)
+    }
+
+    /// Recompute a user's LP position from their LP tokens and the current pool ratio,
+    /// cleaning up stale or inconsistent deposit figures
+    pub fn consolidate_lp_position(env: Env, user: Address) -> Result<Option<LPPosition>, SwapTradeError> {
+        user.require_auth();
+
+        if is_blacklisted(&env, &user) {
+            return Err(SwapTradeError::Blacklisted);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let position = portfolio.consolidate_lp_position(user);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(position)
+    }
+
+    /// Get the LP-token-weighted pool rate at which a user entered (0 if no position)
+    pub fn get_lp_entry_price(env: Env, user: Address) -> u128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio
+            .get_lp_position(user)
+            .map(|position| position.entry_price)
+            .unwrap_or(0)
+    }
+
+    /// Get LP positions for a user
+    /// Returns a Vec containing the user's position if it exists
+    pub fn get_lp_positions(env: Env, user: Address) -> Vec<LPPosition> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        let mut result = Vec::new(&env);
+        if let Some(position) = portfolio.get_lp_position(user) {
+            result.push_back(position);
+        }
+        result
+    }
+
+    /// Get the LP fees a user could claim now, summed across all their positions,
+    /// as (XLM, stable asset)
+    pub fn get_total_pending_lp_rewards(env: Env, user: Address) -> (i128, i128) {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_total_pending_lp_rewards(&env, user)
+    }
+
+    /// Get the number of LPs currently holding a nonzero position
+    pub fn get_lp_count(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Check user has sufficient balance
-        let user_xlm_balance = portfolio.balance_of(&env, Asset::XLM, user.clone());
-        let user_usdc_balance = portfolio.balance_of(&env, Asset::Custom(symbol_short!("USDCSIM")), user.clone());
-        
-        assert!(user_xlm_balance >= xlm_amount, "Insufficient XLM balance");
-        assert!(user_usdc_balance >= usdc_amount, "Insufficient USDC balance");
+        portfolio.get_lp_count()
+    }
 
-        // Calculate LP tokens to mint using constant product AMM formula
-        // If pool is empty, LP tokens = sqrt(xlm * usdc)
-        // Otherwise, LP tokens = (deposit / pool_size) * total_lp_tokens
-        let lp_tokens_minted = if total_lp_tokens == 0 {
-            // First liquidity provider: LP tokens = sqrt(xlm * usdc)
-            // Use integer square root (Babylonian method)
-            let product = (xlm_amount as u128).saturating_mul(usdc_amount as u128);
-            if product == 0 {
-                panic!("Product must be positive");
-            }
-            // Integer square root using Babylonian method
-            let mut guess = product;
-            let mut prev_guess = 0u128;
-            // Limit iterations to prevent infinite loop
-            let mut iterations = 0;
-            while guess != prev_guess && iterations < 100 {
-                prev_guess = guess;
-                let quotient = product / guess;
-                guess = (guess + quotient) / 2;
-                if guess == 0 {
-                    guess = 1;
-                    break;
-                }
-                iterations += 1;
-            }
-            guess as i128
-        } else {
-            // Calculate proportional share
-            // LP tokens = min((xlm_amount / current_xlm) * total_lp_tokens, (usdc_amount / current_usdc) * total_lp_tokens)
-            // This ensures the ratio is maintained
-            let xlm_share = if current_xlm > 0 {
-                (xlm_amount as u128).saturating_mul(total_lp_tokens as u128) / (current_xlm as u128)
-            } else {
-                0
-            };
-            let usdc_share = if current_usdc > 0 {
-                (usdc_amount as u128).saturating_mul(total_lp_tokens as u128) / (current_usdc as u128)
-            } else {
-                0
-            };
-            
-            // Take minimum to maintain ratio
-            core::cmp::min(xlm_share as i128, usdc_share as i128)
-        };
+    /// Get up to `limit` of a user's most recent LP deposits, oldest first
+    pub fn get_lp_deposit_history(env: Env, user: Address, limit: u32) -> Vec<LPDepositRecord> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        assert!(lp_tokens_minted > 0, "LP tokens minted must be positive");
+        portfolio.get_lp_deposit_history(&env, user, limit)
+    }
 
-        // Debit assets from user (transfer to pool)
-        portfolio.debit(&env, Asset::XLM, user.clone(), xlm_amount);
-        portfolio.debit(&env, Asset::Custom(symbol_short!("USDCSIM")), user.clone(), usdc_amount);
+    /// Admin: distribute all accumulated LP fees to current LPs in one shot
+    /// Returns the amounts paid out as (XLM, stable asset); rounding dust stays in the pool
+    pub fn settle_all_lp_fees(env: Env) -> Result<(i128, i128), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
 
-        // Update pool liquidity
-        portfolio.add_pool_liquidity(xlm_amount, usdc_amount);
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Update or create LP position
-        let existing_position = portfolio.get_lp_position(user.clone());
-        let new_position = if let Some(mut pos) = existing_position {
-            // Update existing position
-            pos.xlm_deposited = pos.xlm_deposited.saturating_add(xlm_amount);
-            pos.usdc_deposited = pos.usdc_deposited.saturating_add(usdc_amount);
-            pos.lp_tokens_minted = pos.lp_tokens_minted.saturating_add(lp_tokens_minted);
-            pos
-        } else {
-            // Create new position
-            LPPosition {
-                lp_address: user.clone(),
-                xlm_deposited: xlm_amount,
-                usdc_deposited: usdc_amount,
-                lp_tokens_minted,
-            }
-        };
+        let distributed = portfolio.settle_all_lp_fees(&env);
 
-        portfolio.set_lp_position(user.clone(), new_position);
-        portfolio.add_total_lp_tokens(lp_tokens_minted);
+        env.storage().instance().set(&(), &portfolio);
 
-        // Record LP deposit for badge tracking
-        portfolio.record_lp_deposit(user.clone());
-        portfolio.check_and_award_badges(&env, user.clone());
+        Ok(distributed)
+    }
 
-        // Record rate limit usage
-        RateLimiter::record_lp_op(&env, &user, env.ledger().timestamp());
+    /// Admin: set how long a position must be held before it earns LP fees (anti-JIT)
+    pub fn set_lp_min_hold_seconds(env: Env, seconds: u64) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_lp_min_hold_seconds(seconds);
 
         env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
 
-        lp_tokens_minted
+    /// Get the minimum LP holding time in seconds
+    pub fn get_lp_min_hold_seconds(env: Env) -> u64 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_lp_min_hold_seconds()
     }
 
-    /// Remove liquidity from the pool by burning LP tokens
-    /// Returns (xlm_amount, usdc_amount) returned to user
-    pub fn remove_liquidity(env: Env, lp_tokens: i128, user: Address) -> (i128, i128) {
-        assert!(lp_tokens > 0, "LP tokens must be positive");
+    /// Admin: set the fee (bps) withheld from LP withdrawals; it stays in the pool for remaining LPs
+    pub fn set_lp_exit_fee_bps(env: Env, bps: u32) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
 
         let mut portfolio: Portfolio = env
             .storage()
@@ -512,82 +2220,317 @@ impl CounterContract {
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Get user's LP position
-        let position = portfolio.get_lp_position(user.clone());
-        assert!(position.is_some(), "User has no LP position");
-        let mut pos = position.unwrap();
-
-        // Verify user has enough LP tokens
-        assert!(pos.lp_tokens_minted >= lp_tokens, "Insufficient LP tokens");
+        portfolio.set_lp_exit_fee_bps(bps);
 
-        // Get current pool state
-        let current_xlm = portfolio.get_liquidity(Asset::XLM);
-        let current_usdc = portfolio.get_liquidity(Asset::Custom(symbol_short!("USDCSIM")));
-        let total_lp_tokens = portfolio.get_total_lp_tokens();
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
 
-        assert!(total_lp_tokens > 0, "No LP tokens in pool");
+    /// Get the LP withdrawal fee in bps (0 by default)
+    pub fn get_lp_exit_fee_bps(env: Env) -> u32 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Calculate proportional share of pool
-        // xlm_amount = (lp_tokens / total_lp_tokens) * current_xlm
-        // usdc_amount = (lp_tokens / total_lp_tokens) * current_usdc
-        let xlm_amount = ((lp_tokens as u128).saturating_mul(current_xlm as u128) / (total_lp_tokens as u128)) as i128;
-        let usdc_amount = ((lp_tokens as u128).saturating_mul(current_usdc as u128) / (total_lp_tokens as u128)) as i128;
+        portfolio.get_lp_exit_fee_bps()
+    }
 
-        assert!(xlm_amount > 0 && usdc_amount > 0, "Amounts must be positive");
+    /// Admin: set the smallest LP deposit accepted, valued in the stable asset
+    pub fn set_min_lp_deposit(env: Env, amount: i128) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
 
-        // Verify we're not removing more than deposited (with rounding tolerance)
-        // Allow small rounding differences
-        let max_xlm = pos.xlm_deposited;
-        let max_usdc = pos.usdc_deposited;
-        
-        // Check if removing more than deposited (with 1% tolerance for rounding)
-        if xlm_amount > max_xlm.saturating_mul(101) / 100 || usdc_amount > max_usdc.saturating_mul(101) / 100 {
-            panic!("Cannot remove more than deposited");
+        if amount < 0 {
+            return Err(SwapTradeError::InvalidAmount);
         }
 
-        // Update pool liquidity (subtract)
-        portfolio.set_liquidity(Asset::XLM, current_xlm.saturating_sub(xlm_amount));
-        portfolio.set_liquidity(Asset::Custom(symbol_short!("USDCSIM")), current_usdc.saturating_sub(usdc_amount));
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Transfer assets from pool to user
-        portfolio.mint(&env, Asset::XLM, user.clone(), xlm_amount);
-        portfolio.mint(&env, Asset::Custom(symbol_short!("USDCSIM")), user.clone(), usdc_amount);
+        portfolio.set_min_lp_deposit(amount);
 
-        // Update LP position
-        pos.lp_tokens_minted = pos.lp_tokens_minted.saturating_sub(lp_tokens);
-        pos.xlm_deposited = pos.xlm_deposited.saturating_sub(xlm_amount);
-        pos.usdc_deposited = pos.usdc_deposited.saturating_sub(usdc_amount);
+        env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
 
-        if pos.lp_tokens_minted == 0 {
-            // Remove position if all tokens burned
-            // Note: Map doesn't have remove, so we set to a zero position or track separately
-            // For now, we'll keep it with zero values
-        }
-        portfolio.set_lp_position(user.clone(), pos);
-        portfolio.subtract_total_lp_tokens(lp_tokens);
+    /// Get the smallest LP deposit accepted (1 by default)
+    pub fn get_min_lp_deposit(env: Env) -> i128 {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
 
-        // Record rate limit usage
-        RateLimiter::record_lp_op(&env, &user, env.ledger().timestamp());
+        portfolio.get_min_lp_deposit()
+    }
 
+    /// Admin: cap how many LP positions one user may open; `None` removes the cap.
+    /// Deposits into a position the user already holds are always accepted. With the single
+    /// XLM/stable pool a user holds at most one position, so only a cap of 0 refuses anyone.
+    pub fn set_max_positions_per_user(env: Env, max: Option<u32>) -> Result<(), SwapTradeError> {
+        let caller = env.invoker();
+        caller.require_auth();
+        require_admin(&env, &caller)?;
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.set_max_positions_per_user(max);
         env.storage().instance().set(&(), &portfolio);
+        Ok(())
+    }
 
-        (xlm_amount, usdc_amount)
+    /// Get the cap on LP positions per user (None when unlimited)
+    pub fn get_max_positions_per_user(env: Env) -> Option<u32> {
+        let portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        portfolio.get_max_positions_per_user()
     }
 
-    /// Get LP positions for a user
-    /// Returns a Vec containing the user's position if it exists
-    pub fn get_lp_positions(env: Env, user: Address) -> Vec<LPPosition> {
+    /// Get the number of open LP positions a user holds (0 or 1 with the single pool)
+    pub fn get_user_position_count(env: Env, user: Address) -> u32 {
         let portfolio: Portfolio = env
             .storage()
             .instance()
             .get(&())
             .unwrap_or_else(|| Portfolio::new(&env));
 
-        let mut result = Vec::new(&env);
-        if let Some(position) = portfolio.get_lp_position(user) {
-            result.push_back(position);
+        portfolio.get_user_position_count(user)
+    }
+}
+
+
+impl CounterContract {
+    /// The failure reason `try_swap` records for a rejected swap
+    fn order_failure(err: SwapTradeError) -> OrderFailure {
+        match err {
+            SwapTradeError::TradingPaused => OrderFailure::Paused,
+            SwapTradeError::Blacklisted => OrderFailure::Blacklisted,
+            SwapTradeError::InvalidAmount => OrderFailure::ZeroAmount,
+            SwapTradeError::InvalidSwapPair => OrderFailure::InvalidPair,
+            SwapTradeError::RateLimited => OrderFailure::RateLimited,
+            SwapTradeError::InsufficientLiquidity => OrderFailure::NoLiquidity,
+            SwapTradeError::VolumeCapReached => OrderFailure::VolumeCapReached,
+            SwapTradeError::SlippageProtectionRequired => OrderFailure::SlippageProtectionRequired,
+            SwapTradeError::InsufficientBalance => OrderFailure::InsufficientBalance,
+            SwapTradeError::LowConfidence => OrderFailure::LowConfidence,
+            SwapTradeError::SandwichBlocked => OrderFailure::SandwichBlocked,
+            SwapTradeError::StalePrice => OrderFailure::StalePrice,
+            SwapTradeError::InvalidPrice => OrderFailure::InvalidPrice,
+            SwapTradeError::SlippageExceeded => OrderFailure::SlippageExceeded,
+            _ => OrderFailure::Other,
         }
-        result
+    }
+
+    /// Bookkeeping after a successful swap, shared by every swap path: volume and utilization
+    /// stats, rate-limit and free-swap usage, the swap details, history and trade count
+    fn record_completed_swap(env: &Env, portfolio: &mut Portfolio, user: Address, details: SwapDetails, free_swap: bool) {
+        let from_asset = if details.from_token == symbol_short!("XLM") {
+            Asset::XLM
+        } else {
+            Asset::Custom(details.from_token.clone())
+        };
+        let amount = details.amount_in;
+        portfolio.record_directional_volume(user.clone(), from_asset, amount);
+        portfolio.record_capped_volume(amount);
+        portfolio.record_largest_trade(user.clone(), amount);
+        portfolio.record_utilization(env, amount);
+        RateLimiter::record_swap(env, &user, env.ledger().timestamp());
+        RateLimiter::record_pair_swap(env, &user, &details.from_token, &details.to_token, env.ledger().timestamp());
+        if free_swap {
+            trading::consume_free_swap(env, &user);
+        }
+
+        // Output routed to another address is kept on the transaction but left out of PnL
+        if details.recipient == user {
+            portfolio.record_transaction(
+                env,
+                user.clone(),
+                details.from_token.clone(),
+                details.to_token.clone(),
+                amount,
+                details.amount_out,
+            );
+        } else {
+            portfolio.record_routed_transaction(
+                env,
+                user.clone(),
+                details.from_token.clone(),
+                details.to_token.clone(),
+                amount,
+                details.amount_out,
+                details.recipient.clone(),
+            );
+        }
+        portfolio.record_swap_details(user.clone(), details);
+        portfolio.record_trade(env, user);
+    }
+
+    /// Shared swap execution; `user` pays the input and `recipient` is credited the output.
+    /// `fee_override` replaces the tier-derived fee when set. An explicit `min_out` is the least
+    /// output the caller accepts (0 for none); `None` applies the default slippage tolerance
+    #[allow(clippy::too_many_arguments)]
+    fn execute_swap(
+        env: Env,
+        from: Symbol,
+        to: Symbol,
+        amount: i128,
+        user: Address,
+        recipient: Address,
+        fee_override: Option<u32>,
+        min_out: Option<i128>,
+    ) -> Result<i128, SwapTradeError> {
+        if is_blacklisted(&env, &user) {
+            return Err(SwapTradeError::Blacklisted);
+        }
+        if min_out.unwrap_or(0) <= 0 && trading::requires_slippage_protection(&env) {
+            return Err(SwapTradeError::SlippageProtectionRequired);
+        }
+
+        let mut portfolio: Portfolio = env
+            .storage()
+            .instance()
+            .get(&())
+            .unwrap_or_else(|| Portfolio::new(&env));
+
+        if amount <= 0 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+        if !trading::is_supported_pair(&portfolio, &from, &to) {
+            return Err(SwapTradeError::InvalidSwapPair);
+        }
+
+        let from_asset = if from == symbol_short!("XLM") { Asset::XLM } else { Asset::Custom(from.clone()) };
+
+        // Reject an unaffordable swap before anything is marked or debited
+        if portfolio.balance_of(&env, from_asset, user.clone()) < amount {
+            return Err(SwapTradeError::InsufficientBalance);
+        }
+        if portfolio.exceeds_volume_cap(amount) {
+            return Err(SwapTradeError::VolumeCapReached);
+        }
+
+        if !has_liquidity(&portfolio) {
+            return Err(SwapTradeError::InsufficientLiquidity);
+        }
+
+        match trading::check_oracle_price(&env, &from, &to) {
+            Err(ContractError::StalePrice) => return Err(SwapTradeError::StalePrice),
+            Err(_) => return Err(SwapTradeError::InvalidPrice),
+            Ok(()) => {}
+        }
+
+        // Refuse to trade against an oracle price the feed itself is unsure of, whichever
+        // orientation the pair's price is stored in
+        if let Some((_, confidence)) = trading::price_with_confidence(&env, &from, &to) {
+            if confidence < oracle::MIN_CONFIDENCE_BPS {
+                return Err(SwapTradeError::LowConfidence);
+            }
+        }
+
+        // Get user's current tier for fee calculation and rate limiting
+        let user_tier = portfolio.get_user_tier(&env, user.clone());
+        
+        // Check rate limit before executing swap, including any limit on this pair
+        if RateLimiter::check_pair_swap_limit(&env, &user, &user_tier, &from, &to).is_err() {
+            return Err(SwapTradeError::RateLimited);
+        }
+
+        let reserves_before = (
+            portfolio.get_liquidity(Asset::XLM),
+            portfolio.get_liquidity(portfolio.stable_asset()),
+        );
+
+        let free_swap = fee_override.is_none() && trading::has_free_swap(&env, &portfolio, &user);
+        let fee_bps = fee_override.unwrap_or_else(|| {
+            trading::effective_swap_fee_bps(&env, &portfolio, user.clone(), &from, &to, amount)
+        });
+
+        // Collect the fee on the input amount (rounded up); the rest is swapped
+        let fee_amount = portfolio.apply_swap_fee(&env, user.clone(), &from, &to, amount, fee_bps);
+        let swap_amount = amount - fee_amount;
+
+        // A plain swap (no explicit min) gets the user's default slippage tolerance, or the
+        // contract default, as its min_out; an explicit min_out is used exactly as given
+        let min_out = min_out.unwrap_or_else(|| {
+            let slippage_bps = trading::get_user_slippage_bps(&env, &user).unwrap_or(trading::DEFAULT_SLIPPAGE_BPS);
+            let expected = spot_quote(&portfolio, &from, swap_amount);
+            (expected * (10000 - slippage_bps as i128)) / 10000
+        });
+
+        // The output must leave something in the reserve, whatever the pricing mode
+        let quoted_out = trading::quote_swap_out(&env, &portfolio, &from, &to, swap_amount);
+        if quoted_out >= trading::output_reserve(&portfolio, &from) {
+            return Err(SwapTradeError::InsufficientLiquidity);
+        }
+        if quoted_out <= 0 {
+            return Err(SwapTradeError::InvalidAmount);
+        }
+        if trading::exceeds_max_slippage(&env, &portfolio, &from, &to, swap_amount) {
+            return Err(SwapTradeError::SlippageExceeded);
+        }
+
+        // A large swap can't be paired with an opposing large swap in the same ledger; checked
+        // last so a swap rejected for any other reason doesn't leave a mark
+        if !trading::check_and_mark_large_swap(&env, &user, &from, amount) {
+            return Err(SwapTradeError::SandwichBlocked);
+        }
+
+        let out_amount = perform_swap_to(
+            &env,
+            &mut portfolio,
+            from.clone(),
+            to.clone(),
+            swap_amount,
+            user.clone(),
+            recipient.clone(),
+        );
+
+        if out_amount < min_out {
+            return Err(SwapTradeError::SlippageExceeded);
+        }
+
+        let details = SwapDetails {
+            timestamp: env.ledger().timestamp(),
+            from_token: from,
+            to_token: to,
+            amount_in: amount,
+            fee_amount,
+            amount_out: out_amount,
+            recipient,
+            reserves_before,
+            reserves_after: (
+                portfolio.get_liquidity(Asset::XLM),
+                portfolio.get_liquidity(portfolio.stable_asset()),
+            ),
+        };
+        Self::record_completed_swap(&env, &mut portfolio, user, details, free_swap);
+        env.storage().instance().set(&(), &portfolio);
+
+        // Optional structured logging for successful swap
+        #[cfg(feature = "logging")]
+        {
+            use soroban_sdk::symbol_short;
+            env.events().publish(
+                (symbol_short!("swap")),
+                (amount, out_amount),
+            );
+        }
+
+        Ok(out_amount)
     }
 }
 
@@ -604,6 +2547,14 @@ mod transaction_tests;
 #[cfg(test)]
 mod lp_tests;
 #[cfg(test)]
+mod achievements_tests;
+#[cfg(test)]
+mod admin_tests;
+#[cfg(test)]
+mod prng_tests;
+#[cfg(test)]
+mod asset_tests;
+#[cfg(test)]
 mod enhanced_trading_tests;  // NEW: Enhanced trading tests for better coverage
 mod migration_tests;
 
@@ -1,5 +1,6 @@
 use super::*;
-use soroban_sdk::{symbol_short, testutils::Address as _, Address, Env, Symbol, Vec};
+use crate::admin_tests::setup_admin;
+use soroban_sdk::{symbol_short, testutils::{Address as _, Ledger as _}, Address, Env, Symbol, Vec};
 use crate::portfolio::{Asset, LPPosition};
 
 #[test]
@@ -189,13 +190,14 @@ fn test_lp_fee_collection() {
     client.mint(&symbol_short!("XLM"), &trader, &1000);
     client.set_price(&(symbol_short!("XLM"), symbol_short!("USDCSIM")), &1_000_000_000_000_000_000);
 
-    // Perform 10 swaps
-    for _ in 0..10 {
+    // Perform 10 swaps, an hour apart to stay within the swap rate limit
+    for i in 0..10 {
+        env.ledger().set_timestamp(i * 3_600);
         client.swap(&symbol_short!("XLM"), &symbol_short!("USDCSIM"), &10, &trader);
     }
 
     // Fees should be accumulated (0.3% of each swap)
-    // This is tracked in the portfolio's lp_fees_accumulated
+    // This is tracked per input asset in the portfolio's lp_fees_accumulated
     // Note: We can't directly check this without a getter, but the fees are being collected
 }
 
@@ -305,3 +307,835 @@ fn test_lp_share_calculations() {
     assert!(lp_tokens2 >= lp_tokens1 * 2 - 2, "User2 should have approximately double LP tokens");
     assert!(lp_tokens2 <= lp_tokens1 * 2 + 2, "User2 should have approximately double LP tokens");
 }
+
+#[test]
+fn test_settle_all_lp_fees_distributes_pro_rata() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let lp1 = Address::generate(&env);
+    let lp2 = Address::generate(&env);
+    let lp3 = Address::generate(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+
+    // Three LPs holding 100 / 200 / 300 of 600 LP tokens
+    for (lp, tokens) in [(lp1.clone(), 100), (lp2.clone(), 200), (lp3.clone(), 300)] {
+        portfolio.set_lp_position(lp.clone(), LPPosition {
+            lp_address: lp,
+            xlm_deposited: tokens,
+            usdc_deposited: tokens,
+            lp_tokens_minted: tokens,
+            entry_price: 0,
+        });
+        portfolio.add_total_lp_tokens(tokens);
+    }
+    portfolio.add_lp_fees(usdc.clone(), 1000);
+    let usdc_pool_before = portfolio.get_liquidity(usdc.clone());
+
+    let distributed = portfolio.settle_all_lp_fees(&env);
+
+    // 1000 * 100/600 = 166, 1000 * 200/600 = 333, 1000 * 300/600 = 500
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp1), 166);
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp2), 333);
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp3), 500);
+    assert_eq!(distributed, (0, 999));
+
+    // The 1 unit of dust stays in the pool and the accumulator is cleared
+    assert_eq!(portfolio.get_liquidity(usdc.clone()), usdc_pool_before + 1);
+    assert_eq!(portfolio.get_lp_fees_accumulated(usdc), 0);
+}
+
+#[test]
+fn test_settle_all_lp_fees_pays_each_asset_in_kind() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let lp1 = Address::generate(&env);
+    let lp2 = Address::generate(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+
+    for (lp, tokens) in [(lp1.clone(), 100), (lp2.clone(), 200)] {
+        portfolio.set_lp_position(lp.clone(), LPPosition {
+            lp_address: lp,
+            xlm_deposited: tokens,
+            usdc_deposited: tokens,
+            lp_tokens_minted: tokens,
+            entry_price: 0,
+        });
+        portfolio.add_total_lp_tokens(tokens);
+    }
+    portfolio.add_lp_fees(Asset::XLM, 100);
+    portfolio.add_lp_fees(usdc.clone(), 300);
+    let xlm_pool_before = portfolio.get_liquidity(Asset::XLM);
+    let usdc_pool_before = portfolio.get_liquidity(usdc.clone());
+
+    assert_eq!(portfolio.settle_all_lp_fees(&env), (99, 300));
+
+    // XLM fees come back as XLM, USDCSIM fees as USDCSIM
+    assert_eq!(portfolio.balance_of(&env, Asset::XLM, lp1.clone()), 33);
+    assert_eq!(portfolio.balance_of(&env, Asset::XLM, lp2.clone()), 66);
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp1), 100);
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp2), 200);
+
+    // XLM dust goes back to the XLM reserve only
+    assert_eq!(portfolio.get_liquidity(Asset::XLM), xlm_pool_before + 1);
+    assert_eq!(portfolio.get_liquidity(usdc), usdc_pool_before);
+}
+
+#[test]
+fn test_settle_all_lp_fees_credits_protocol_share() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let lp = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+
+    portfolio.set_lp_position(lp.clone(), LPPosition {
+        lp_address: lp.clone(),
+        xlm_deposited: 300,
+        usdc_deposited: 300,
+        lp_tokens_minted: 300,
+        entry_price: 0,
+    });
+    portfolio.add_total_lp_tokens(300);
+    // The protocol seeds 100 of the 400 LP tokens
+    portfolio.mint(&env, Asset::XLM, admin.clone(), 100);
+    portfolio.mint(&env, usdc.clone(), admin.clone(), 100);
+    portfolio.seed_protocol_liquidity(&env, admin, 100, 100);
+    assert_eq!(portfolio.get_protocol_lp_tokens(), 100);
+
+    portfolio.add_lp_fees(usdc.clone(), 1000);
+    let usdc_pool_before = portfolio.get_liquidity(usdc.clone());
+
+    assert_eq!(portfolio.settle_all_lp_fees(&env), (0, 750));
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp), 750);
+
+    // The protocol's quarter is held as protocol fees rather than folded into the reserve
+    assert_eq!(portfolio.get_protocol_fees(usdc.clone()), 250);
+    assert_eq!(portfolio.get_liquidity(usdc.clone()), usdc_pool_before);
+    assert_eq!(portfolio.get_lp_fees_accumulated(usdc), 0);
+}
+
+#[test]
+fn test_lp_fees_withheld_until_min_hold_time() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let lp = Address::generate(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+
+    portfolio.set_lp_min_hold_seconds(3600);
+
+    env.ledger().set_timestamp(1_000);
+    portfolio.set_lp_position(lp.clone(), LPPosition {
+        lp_address: lp.clone(),
+        xlm_deposited: 100,
+        usdc_deposited: 100,
+        lp_tokens_minted: 100,
+        entry_price: 0,
+    });
+    portfolio.add_total_lp_tokens(100);
+    portfolio.record_lp_deposit_time(&env, lp.clone());
+    portfolio.add_lp_fees(usdc.clone(), 1000);
+
+    // Settling right after the deposit pays nothing and keeps the fees
+    assert_eq!(portfolio.settle_all_lp_fees(&env), (0, 0));
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp.clone()), 0);
+    assert_eq!(portfolio.get_lp_fees_accumulated(usdc.clone()), 1000);
+
+    // Once the minimum holding time has passed the position is paid
+    env.ledger().set_timestamp(4_600);
+    assert_eq!(portfolio.settle_all_lp_fees(&env), (0, 1000));
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp), 1000);
+    assert_eq!(portfolio.get_lp_fees_accumulated(usdc), 0);
+}
+
+#[test]
+fn test_spread_widens_with_trade_size() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &lp, &2_000_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &2_000_000);
+    client.add_liquidity(&1_000_000, &1_000_000, &lp);
+
+    // Buying 1_000 costs 1_006 XLM, selling 1_000 returns 996 XLM -> 10/1006 = 99 bps
+    let small = client.get_spread_bps(&1_000);
+    assert_eq!(small, 99);
+
+    // Buying 100_000 costs 111_447 XLM, selling returns 90_661 XLM -> 1865 bps
+    let large = client.get_spread_bps(&100_000);
+    assert_eq!(large, 1865);
+    assert!(large > small, "Spread should widen with larger amounts");
+}
+
+#[test]
+fn test_spread_empty_pool_is_zero() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    assert_eq!(client.get_spread_bps(&100), 0);
+}
+
+#[test]
+fn test_depth_to_price_matches_constant_product() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    // 1M XLM / 4M USDCSIM -> spot rate 4.0 (40_000_000 at 1e7 scale)
+    client.mint(&symbol_short!("XLM"), &lp, &1_000_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &4_000_000);
+    client.add_liquidity(&1_000_000, &4_000_000, &lp);
+
+    // Down to 1.0: k = 4e12, x' = sqrt(4e12 / 1) = 2M, so 1M XLM net of fee
+    let expected_xlm = (1_000_000i128 * 10000 + 9969) / 9970;
+    assert_eq!(client.get_depth_to_price(&10_000_000), expected_xlm);
+
+    // Up to 9.0: y' = sqrt(4e12 * 9) = 6M, so 2M USDCSIM net of fee
+    let expected_usdc = (2_000_000i128 * 10000 + 9969) / 9970;
+    assert_eq!(client.get_depth_to_price(&90_000_000), expected_usdc);
+
+    // Already at the target
+    assert_eq!(client.get_depth_to_price(&40_000_000), 0);
+}
+
+#[test]
+fn test_add_liquidity_blocked_while_paused() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &user, &1000);
+    client.mint(&symbol_short!("USDCSIM"), &user, &1000);
+
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&PAUSED_KEY, &true);
+    });
+
+    let result = client.try_add_liquidity(&100, &100, &user);
+    assert_eq!(result, Err(Ok(SwapTradeError::TradingPaused)));
+
+    // Nothing was debited
+    assert_eq!(client.balance_of(&symbol_short!("XLM"), &user), 1000);
+    assert_eq!(client.get_lp_positions(&user).len(), 0);
+}
+
+#[test]
+fn test_remove_liquidity_allowed_while_paused() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &user, &1000);
+    client.mint(&symbol_short!("USDCSIM"), &user, &1000);
+    let lp_tokens = client.add_liquidity(&100, &100, &user);
+
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&PAUSED_KEY, &true);
+    });
+
+    // LPs can still exit during a pause
+    let (xlm, usdc) = client.remove_liquidity(&lp_tokens, &user);
+    assert!(xlm > 0 && usdc > 0);
+}
+
+#[test]
+fn test_lp_entry_price_is_weighted_average() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &lp, &5000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &5000);
+
+    // First deposit at 1 USDCSIM per XLM mints 1000 LP tokens
+    client.add_liquidity(&1000, &1000, &lp);
+    assert_eq!(client.get_lp_entry_price(&lp), 10_000_000);
+
+    // Move the pool to 2 USDCSIM per XLM
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.add_pool_liquidity(0, 1000);
+        env.storage().instance().set(&(), &portfolio);
+    });
+
+    // Second deposit at the new rate mints 100 LP tokens
+    let minted = client.add_liquidity(&100, &200, &lp);
+    assert_eq!(minted, 100);
+
+    // (1.0 * 1000 + 2.0 * 100) / 1100
+    assert_eq!(client.get_lp_entry_price(&lp), 10_909_090);
+}
+
+#[test]
+fn test_consolidate_lp_position_cleans_inconsistent_state() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+    let stranger = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &lp, &1000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &1000);
+    client.add_liquidity(&1000, &1000, &lp);
+
+    // Craft a position whose deposit figures have drifted from its LP tokens
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        let mut pos = portfolio.get_lp_position(lp.clone()).unwrap();
+        pos.lp_address = stranger.clone();
+        pos.xlm_deposited = 1_200;
+        pos.usdc_deposited = -5;
+        portfolio.set_lp_position(lp.clone(), pos);
+        // Pool has since moved to 1000 XLM / 2000 USDCSIM
+        portfolio.add_pool_liquidity(0, 1000);
+        env.storage().instance().set(&(), &portfolio);
+    });
+
+    let pos = client.consolidate_lp_position(&lp).unwrap();
+    assert_eq!(pos.lp_address, lp);
+    assert_eq!(pos.lp_tokens_minted, 1000);
+    assert_eq!(pos.xlm_deposited, 1000);
+    assert_eq!(pos.usdc_deposited, 2000);
+    assert_eq!(pos.entry_price, 10_000_000);
+
+    // The cleaned position is persisted
+    assert_eq!(client.get_lp_positions(&lp).get(0).unwrap(), pos);
+
+    // Addresses without a position have nothing to consolidate
+    assert_eq!(client.consolidate_lp_position(&stranger), None);
+
+    // Blacklisted LPs can't rewrite their position
+    setup_admin(&env, &contract_id);
+    client.blacklist_user(&lp);
+    assert_eq!(client.try_consolidate_lp_position(&lp), Err(Ok(SwapTradeError::Blacklisted)));
+}
+
+#[test]
+fn test_remove_all_liquidity_matches_full_removal() {
+    let setup = || {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(CounterContract, ());
+        let client = CounterContractClient::new(&env, &contract_id);
+        let lp = Address::generate(&env);
+        let other = Address::generate(&env);
+        for (user, amount) in [(lp.clone(), 10_000), (other, 5_000)] {
+            client.mint(&symbol_short!("XLM"), &user, &amount);
+            client.mint(&symbol_short!("USDCSIM"), &user, &amount);
+            client.add_liquidity(&amount, &amount, &user);
+        }
+        (env, contract_id, lp)
+    };
+
+    let (env_a, id_a, lp_a) = setup();
+    let client_a = CounterContractClient::new(&env_a, &id_a);
+    let tokens = client_a.get_lp_positions(&lp_a).get(0).unwrap().lp_tokens_minted;
+    let explicit = client_a.remove_liquidity(&tokens, &lp_a);
+
+    let (env_b, id_b, lp_b) = setup();
+    let client_b = CounterContractClient::new(&env_b, &id_b);
+    let all = client_b.remove_all_liquidity(&lp_b);
+
+    assert_eq!(all, explicit);
+    assert_eq!(all, (10_000, 10_000));
+
+    let position = client_b.get_lp_positions(&lp_b).get(0).unwrap();
+    assert_eq!(position.lp_tokens_minted, 0);
+    assert_eq!(position.xlm_deposited, 0);
+    assert_eq!(position.usdc_deposited, 0);
+    assert_eq!(client_b.get_lp_count(), 1);
+
+    // Nothing left to withdraw
+    assert_eq!(client_b.try_remove_all_liquidity(&lp_b), Err(Ok(SwapTradeError::InvalidAmount)));
+}
+
+#[test]
+fn test_tvl_values_xlm_at_pool_rate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    assert_eq!(client.get_tvl_usdc(), 0);
+
+    client.mint(&symbol_short!("XLM"), &lp, &30_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &12_000);
+    client.add_liquidity(&30_000, &12_000, &lp);
+
+    // 30_000 XLM at 0.4 USDCSIM each, plus the 12_000 USDCSIM reserve
+    let xlm_value = 30_000 * 12_000 / 30_000;
+    assert_eq!(client.get_tvl_usdc(), xlm_value + 12_000);
+    assert_eq!(client.get_tvl_usdc(), 24_000);
+}
+
+#[test]
+fn test_pair_reserves_follow_requested_order() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &10_000);
+    client.mint(&usdc, &lp, &10_000);
+    client.add_liquidity(&6_000, &2_000, &lp);
+
+    assert_eq!(client.get_pair_reserves(&xlm, &usdc), (6_000, 2_000));
+    assert_eq!(client.get_pair_reserves(&usdc, &xlm), (2_000, 6_000));
+
+    // XLM/stable is the only pool, so every other pair reports empty reserves
+    assert_eq!(client.get_pair_reserves(&xlm, &symbol_short!("BTC")), (0, 0));
+    assert_eq!(client.get_pair_reserves(&usdc, &symbol_short!("BTC")), (0, 0));
+    assert_eq!(client.get_pair_reserves(&xlm, &xlm), (0, 0));
+}
+
+#[test]
+fn test_lp_exit_fee_stays_in_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let leaver = Address::generate(&env);
+    let stayer = Address::generate(&env);
+    for lp in [leaver.clone(), stayer.clone()] {
+        client.mint(&symbol_short!("XLM"), &lp, &10_000);
+        client.mint(&symbol_short!("USDCSIM"), &lp, &10_000);
+        client.add_liquidity(&10_000, &10_000, &lp);
+    }
+
+    assert_eq!(client.get_lp_exit_fee_bps(), 0);
+    client.set_lp_exit_fee_bps(&100);
+
+    // The 10_000 / 10_000 share is returned less 1%
+    let (xlm_out, usdc_out) = client.remove_liquidity(&10_000, &leaver);
+    assert_eq!((xlm_out, usdc_out), (9_900, 9_900));
+
+    // The withheld 100 of each asset remains backing the other LP
+    let reserves = env.as_contract(&contract_id, || {
+        let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.get_pool_stats()
+    });
+    assert_eq!((reserves.0, reserves.1), (10_100, 10_100));
+}
+
+#[test]
+fn test_lp_operations_use_configured_stable_symbol() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let lp = Address::generate(&env);
+
+    assert_eq!(client.get_stable_symbol(), symbol_short!("USDCSIM"));
+    client.set_stable_symbol(&usdc);
+    assert_eq!(client.get_stable_symbol(), usdc);
+
+    client.mint(&xlm, &lp, &5_000);
+    client.mint(&usdc, &lp, &5_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &5_000);
+
+    client.add_liquidity(&4_000, &2_000, &lp);
+    assert_eq!(client.balance_of(&usdc, &lp), 3_000);
+    assert_eq!(client.balance_of(&symbol_short!("USDCSIM"), &lp), 5_000);
+    assert_eq!(client.get_pair_reserves(&xlm, &usdc), (4_000, 2_000));
+
+    let (xlm_out, usdc_out) = client.remove_liquidity(&client.get_lp_positions(&lp).get(0).unwrap().lp_tokens_minted, &lp);
+    assert_eq!((xlm_out, usdc_out), (4_000, 2_000));
+    assert_eq!(client.balance_of(&usdc, &lp), 5_000);
+    assert_eq!(client.balance_of(&symbol_short!("USDCSIM"), &lp), 5_000);
+}
+
+#[test]
+#[should_panic(expected = "Pool already has liquidity")]
+fn test_stable_symbol_fixed_once_pool_is_funded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    client.mint(&symbol_short!("XLM"), &lp, &1_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &1_000);
+    client.add_liquidity(&1_000, &1_000, &lp);
+
+    client.set_stable_symbol(&symbol_short!("USDC"));
+}
+
+#[test]
+fn test_remove_liquidity_with_zero_total_lp_tokens_is_typed_error() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    // Corrupt state: a funded position while the LP token total is zero
+    env.as_contract(&contract_id, || {
+        let mut portfolio = Portfolio::new(&env);
+        portfolio.set_lp_position(lp.clone(), LPPosition {
+            lp_address: lp.clone(),
+            xlm_deposited: 1_000,
+            usdc_deposited: 1_000,
+            lp_tokens_minted: 1_000,
+            entry_price: 10_000_000,
+        });
+        portfolio.add_pool_liquidity(1_000, 1_000);
+        env.storage().instance().set(&(), &portfolio);
+    });
+
+    assert_eq!(client.try_remove_liquidity(&500, &lp), Err(Ok(SwapTradeError::NoLiquidity)));
+
+    // The admin repair restores a consistent total and withdrawals work again
+    assert_eq!(client.reset_lp_accounting(), 1_000);
+    assert_eq!(client.remove_liquidity(&500, &lp), (500, 500));
+}
+
+#[test]
+fn test_pool_composition_bps() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    assert_eq!(client.get_pool_composition_bps(), (0, 0));
+
+    client.mint(&symbol_short!("XLM"), &lp, &4_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &1_000);
+    client.add_liquidity(&4_000, &1_000, &lp);
+
+    // Valued at its own rate the pool is always an even split
+    assert_eq!(client.get_pool_composition_bps(), (5000, 5000));
+
+    // The oracle values XLM at 0.5: 2_000 of XLM against 1_000 USDCSIM
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(
+            &env,
+            (symbol_short!("XLM"), symbol_short!("USDCSIM")),
+            500_000_000_000_000_000,
+            None,
+        );
+    });
+    assert_eq!(client.get_pool_composition_bps(), (6666, 3334));
+}
+
+#[test]
+fn test_compound_protocol_fees_grows_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &lp, &30_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &10_000);
+    client.add_liquidity(&30_000, &10_000, &lp);
+
+    let pool = || {
+        env.as_contract(&contract_id, || {
+            let portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+            (portfolio.get_pool_stats(), portfolio.get_total_lp_tokens(), portfolio.get_protocol_lp_tokens())
+        })
+    };
+    let (_, lp_before, _) = pool();
+
+    env.as_contract(&contract_id, || {
+        let mut portfolio: Portfolio = env.storage().instance().get(&()).unwrap();
+        portfolio.collect_fee(400);
+        portfolio.record_protocol_fee(Asset::XLM, 300);
+        portfolio.record_protocol_fee(Asset::Custom(symbol_short!("USDCSIM")), 100);
+        env.storage().instance().set(&(), &portfolio);
+    });
+
+    let minted = client.compound_protocol_fees();
+
+    // 300 XLM + 100 USDCSIM matches the 3:1 reserves and is worth 1% of the pool
+    let ((xlm, usdc, fees), lp_after, protocol_lp) = pool();
+    assert_eq!((xlm, usdc), (30_300, 10_100));
+    // Lifetime fee stats are untouched; the compounded value is tracked on its own
+    assert_eq!(fees, 400);
+    assert_eq!(client.get_protocol_fees_compounded(), 200);
+    assert_eq!(minted, lp_before / 100);
+    assert_eq!(protocol_lp, minted);
+    assert_eq!(lp_after, lp_before + minted);
+
+    // Nothing left to compound
+    assert_eq!(client.compound_protocol_fees(), 0);
+}
+
+#[test]
+fn test_compound_protocol_fees_keeps_reserve_ratio() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+    portfolio.add_pool_liquidity(30_000, 10_000);
+    portfolio.add_total_lp_tokens(10_000);
+
+    // Only XLM fees: nothing can go in without moving the price, so all of it stays held
+    portfolio.record_protocol_fee(Asset::XLM, 600);
+    assert_eq!(portfolio.compound_protocol_fees(), 0);
+    assert_eq!(portfolio.get_liquidity(Asset::XLM), 30_000);
+    assert_eq!(portfolio.get_protocol_fees(Asset::XLM), 600);
+
+    // With 100 USDCSIM, 300 XLM pairs with it at 3:1; the other 300 XLM is kept
+    portfolio.record_protocol_fee(usdc.clone(), 100);
+    let minted = portfolio.compound_protocol_fees();
+
+    assert_eq!(portfolio.get_liquidity(Asset::XLM), 30_300);
+    assert_eq!(portfolio.get_liquidity(usdc.clone()), 10_100);
+    // 300 XLM + 100 USDCSIM is 1% of the pool
+    assert_eq!(minted, 100);
+    assert_eq!(portfolio.get_protocol_fees(Asset::XLM), 300);
+    assert_eq!(portfolio.get_protocol_fees(usdc), 0);
+    assert_eq!(portfolio.get_protocol_fees_compounded(), 200);
+}
+
+#[test]
+fn test_protocol_lp_pnl_tracks_price_moves() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let admin = setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    let user_lp = client.add_liquidity(&100_000, &100_000, &lp);
+
+    // The seed must be funded by the admin
+    assert_eq!(
+        client.try_seed_protocol_liquidity(&100_000, &100_000),
+        Err(Ok(SwapTradeError::InsufficientBalance))
+    );
+    client.mint(&xlm, &admin, &100_000);
+    client.mint(&usdc, &admin, &100_000);
+
+    // The protocol matches the user's deposit, so it owns half the pool at no P&L
+    assert_eq!(client.seed_protocol_liquidity(&100_000, &100_000), user_lp);
+    assert_eq!(client.get_protocol_lp_pnl(), 0);
+    assert_eq!(client.balance_of(&xlm, &admin), 0);
+    assert_eq!(client.balance_of(&usdc, &admin), 0);
+    assert_eq!(client.get_pair_reserves(&xlm, &usdc), (200_000, 200_000));
+
+    // Heavy XLM selling drops the XLM price; the protocol's half is now worth less than 200k
+    let trader = Address::generate(&env);
+    client.mint(&xlm, &trader, &200_000);
+    client.swap(&xlm, &usdc, &50_000, &trader);
+
+    let (xlm_reserve, usdc_reserve) = client.get_pair_reserves(&xlm, &usdc);
+    let half_xlm_value = (xlm_reserve / 2) * usdc_reserve / xlm_reserve;
+    let expected = half_xlm_value + usdc_reserve / 2 - 200_000;
+    assert!(expected < 0);
+    assert_eq!(client.get_protocol_lp_pnl(), expected);
+}
+
+#[test]
+fn test_min_lp_deposit_enforced() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    client.mint(&symbol_short!("XLM"), &lp, &20_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &20_000);
+    client.add_liquidity(&10_000, &10_000, &lp);
+
+    assert_eq!(client.get_min_lp_deposit(), 1);
+    client.set_min_lp_deposit(&1_000);
+
+    // 499 + 499 at a 1:1 pool is worth 998
+    assert_eq!(client.try_add_liquidity(&499, &499, &lp), Err(Ok(SwapTradeError::DepositTooSmall)));
+    client.add_liquidity(&500, &500, &lp);
+}
+
+#[test]
+fn test_lp_count_tracks_active_providers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp1 = Address::generate(&env);
+    let lp2 = Address::generate(&env);
+    for lp in [lp1.clone(), lp2.clone()] {
+        client.mint(&symbol_short!("XLM"), &lp, &10_000);
+        client.mint(&symbol_short!("USDCSIM"), &lp, &10_000);
+        client.add_liquidity(&5_000, &5_000, &lp);
+    }
+    assert_eq!(client.get_lp_count(), 2);
+
+    // Topping up doesn't count twice
+    client.add_liquidity(&1_000, &1_000, &lp2);
+    assert_eq!(client.get_lp_count(), 2);
+
+    client.remove_liquidity(&5_000, &lp1);
+    assert_eq!(client.get_lp_count(), 1);
+}
+
+#[test]
+fn test_lp_deposit_history_records_each_deposit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    client.mint(&symbol_short!("XLM"), &lp, &20_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &20_000);
+
+    env.ledger().set_timestamp(1_000);
+    let first_tokens = client.add_liquidity(&10_000, &10_000, &lp);
+    env.ledger().set_timestamp(2_000);
+    let second_tokens = client.add_liquidity(&4_000, &4_000, &lp);
+
+    let history = client.get_lp_deposit_history(&lp, &10);
+    assert_eq!(history.len(), 2);
+    let first = history.get(0).unwrap();
+    assert_eq!((first.timestamp, first.xlm_amount, first.usdc_amount), (1_000, 10_000, 10_000));
+    assert_eq!(first.lp_tokens_minted, first_tokens);
+    let second = history.get(1).unwrap();
+    assert_eq!((second.timestamp, second.xlm_amount, second.usdc_amount), (2_000, 4_000, 4_000));
+    assert_eq!(second.lp_tokens_minted, second_tokens);
+
+    // The limit keeps the most recent deposits
+    let latest = client.get_lp_deposit_history(&lp, &1);
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest.get(0).unwrap().timestamp, 2_000);
+}
+
+#[test]
+fn test_total_pending_lp_rewards_matches_settlement() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let lp1 = Address::generate(&env);
+    let lp2 = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    let usdc = Asset::Custom(symbol_short!("USDCSIM"));
+
+    for (lp, tokens) in [(lp1.clone(), 100), (lp2.clone(), 300)] {
+        portfolio.set_lp_position(lp.clone(), LPPosition {
+            lp_address: lp,
+            xlm_deposited: tokens,
+            usdc_deposited: tokens,
+            lp_tokens_minted: tokens,
+            entry_price: 0,
+        });
+        portfolio.add_total_lp_tokens(tokens);
+    }
+    portfolio.add_lp_fees(Asset::XLM, 400);
+    portfolio.add_lp_fees(usdc.clone(), 1000);
+
+    assert_eq!(portfolio.get_total_pending_lp_rewards(&env, lp1.clone()), (100, 250));
+    assert_eq!(portfolio.get_total_pending_lp_rewards(&env, lp2.clone()), (300, 750));
+    assert_eq!(portfolio.get_total_pending_lp_rewards(&env, outsider), (0, 0));
+
+    // Settlement pays exactly what was pending, leaving nothing behind
+    portfolio.settle_all_lp_fees(&env);
+    assert_eq!(portfolio.balance_of(&env, Asset::XLM, lp1.clone()), 100);
+    assert_eq!(portfolio.balance_of(&env, usdc.clone(), lp1.clone()), 250);
+    assert_eq!(portfolio.balance_of(&env, usdc, lp2.clone()), 750);
+    assert_eq!(portfolio.get_total_pending_lp_rewards(&env, lp1), (0, 0));
+    assert_eq!(portfolio.get_total_pending_lp_rewards(&env, lp2), (0, 0));
+}
+
+#[test]
+fn test_u128_to_i128_rejects_out_of_range() {
+    assert_eq!(trading::u128_to_i128(12_345), Ok(12_345));
+    assert_eq!(trading::u128_to_i128(i128::MAX as u128), Ok(i128::MAX));
+
+    // A plain `as i128` cast would wrap these to negative amounts
+    assert_eq!(trading::u128_to_i128(i128::MAX as u128 + 1), Err(SwapTradeError::AmountOverflow));
+    assert_eq!(trading::u128_to_i128(u128::MAX), Err(SwapTradeError::AmountOverflow));
+}
+
+#[cfg(feature = "logging")]
+#[test]
+fn test_remove_liquidity_emits_lp_removed_event() {
+    use soroban_sdk::{testutils::Events as _, IntoVal, TryFromVal};
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let lp = Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &lp, &10_000);
+    client.mint(&symbol_short!("USDCSIM"), &lp, &10_000);
+    let lp_tokens = client.add_liquidity(&10_000, &10_000, &lp);
+    let (xlm_out, usdc_out) = client.remove_liquidity(&(lp_tokens / 2), &lp);
+
+    let (emitter, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(emitter, contract_id);
+    assert_eq!(topics, (Symbol::new(&env, "lp_removed"),).into_val(&env));
+    let data = <(Address, i128, i128, i128)>::try_from_val(&env, &data).unwrap();
+    assert_eq!(data, (lp, xlm_out, usdc_out, lp_tokens / 2));
+}
+
+#[test]
+fn test_max_positions_per_user_rejects_new_positions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    setup_admin(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    let lp = Address::generate(&env);
+    let late = Address::generate(&env);
+    for user in [lp.clone(), late.clone()] {
+        client.mint(&xlm, &user, &20_000);
+        client.mint(&usdc, &user, &20_000);
+    }
+
+    assert_eq!(client.get_max_positions_per_user(), None);
+    client.set_max_positions_per_user(&Some(1));
+
+    // Up to the limit: the single pool gives each user at most one position
+    client.add_liquidity(&10_000, &10_000, &lp);
+    assert_eq!(client.get_user_position_count(&lp), 1);
+    // Topping up the open position doesn't count as a new one
+    client.add_liquidity(&1_000, &1_000, &lp);
+    assert_eq!(client.get_user_position_count(&lp), 1);
+
+    // Then reject: once the cap is below what a new user would open, their first deposit is refused
+    client.set_max_positions_per_user(&Some(0));
+    assert_eq!(client.try_add_liquidity(&1_000, &1_000, &late), Err(Ok(SwapTradeError::TooManyPositions)));
+    assert_eq!(client.balance_of(&xlm, &late), 20_000);
+    assert_eq!(client.get_user_position_count(&late), 0);
+    // Existing positions can still be topped up
+    client.add_liquidity(&1_000, &1_000, &lp);
+
+    // A closed position no longer counts, so reopening it is a new position
+    client.remove_all_liquidity(&lp);
+    assert_eq!(client.get_user_position_count(&lp), 0);
+    assert_eq!(client.try_add_liquidity(&1_000, &1_000, &lp), Err(Ok(SwapTradeError::TooManyPositions)));
+
+    client.set_max_positions_per_user(&None);
+    client.add_liquidity(&1_000, &1_000, &late);
+}
@@ -1,24 +1,122 @@
-use soroban_sdk::{Env, Symbol};
-use crate::portfolio::Portfolio;
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol, Vec};
+use crate::portfolio::{Asset, Badge, Metrics, Portfolio};
+use crate::errors::SwapTradeError;
+use crate::{CONTRACT_VERSION, CONTRACT_VERSION_MINOR};
+
+/// Version details for client compatibility checks
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionInfo {
+    /// Major version of the deployed code (`CONTRACT_VERSION`)
+    pub major: u32,
+    /// Minor version of the deployed code
+    pub minor: u32,
+    /// Version recorded in storage
+    pub stored_version: u32,
+    /// True while the stored version is behind the code version
+    pub migration_pending: bool,
+}
+
+#[contracttype]
+pub enum MigrationKey {
+    MetricsSnapshot(u32),
+}
+
+/// `Metrics` as stored by V1, before the per-reason failure counters
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsV1 {
+    pub trades_executed: u32,
+    pub failed_orders: u32,
+    pub balances_updated: u32,
+}
+
+/// `LPPosition` as stored by V1, before entry prices were tracked
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LPPositionV1 {
+    pub lp_address: Address,
+    pub xlm_deposited: i128,
+    pub usdc_deposited: i128,
+    pub lp_tokens_minted: i128,
+}
+
+/// `Transaction` as stored by V1, before routed swaps recorded their recipient
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionV1 {
+    pub timestamp: u64,
+    pub from_token: Symbol,
+    pub to_token: Symbol,
+    pub from_amount: i128,
+    pub to_amount: i128,
+    pub rate_achieved: u128,
+    pub migration_time: Option<u64>,
+}
+
+/// The `Portfolio` layout stored by V1. Fields added to `Portfolio` since then mean V1
+/// storage no longer decodes as `Portfolio`; `migrate_from_v1_to_v2` decodes it as this
+/// and rewrites it with `Portfolio::from_v1`.
+#[contracttype]
+#[derive(Clone)]
+pub struct PortfolioV1 {
+    pub balances: Map<(Address, Asset), i128>,
+    pub trades: Map<Address, u32>,
+    pub pnl: Map<Address, i128>,
+    pub badges: Map<(Address, Badge), bool>,
+    pub metrics: MetricsV1,
+    pub total_users: u32,
+    pub total_trading_volume: i128,
+    pub active_users: Vec<Address>,
+    pub top_traders: Vec<(Address, i128)>,
+    pub xlm_in_pool: i128,
+    pub usdc_in_pool: i128,
+    pub total_fees_collected: i128,
+    pub initial_balances: Map<Address, i128>,
+    pub token_pairs_traded: Map<Address, Vec<Symbol>>,
+    pub ledger_heights_traded: Map<Address, Vec<u64>>,
+    pub lp_deposits_count: Map<Address, u32>,
+    pub transactions: Map<Address, Vec<TransactionV1>>,
+    pub lp_positions: Map<Address, LPPositionV1>,
+    pub total_lp_tokens: i128,
+    pub lp_fees_accumulated: i128,
+}
+
+/// Load the portfolio for migration: storage still on an older version holds the V1 layout
+/// (unversioned storage predates versioning and is V1 data) and is converted in memory.
+fn load_portfolio_for_migration(env: &Env) -> Portfolio {
+    if get_stored_version(env) < CONTRACT_VERSION {
+        if let Some(v1) = env.storage().instance().get::<_, PortfolioV1>(&()) {
+            return Portfolio::from_v1(env, v1);
+        }
+    }
+    env.storage()
+        .instance()
+        .get(&())
+        .unwrap_or_else(|| Portfolio::new(env))
+}
 
 pub fn migrate_from_v1_to_v2(env: &Env) -> Result<(), u32> {
     // 1. Check current version
     let current_version = get_stored_version(env);
     
-    // If already V2, return success (idempotency)
-    if current_version >= 2 {
+    // If already current, return success (idempotency)
+    if current_version >= CONTRACT_VERSION {
         return Ok(());
     }
 
     // 2. Perform data migration
-    // We load the portfolio. In a real upgrade, if the struct layout changed incompatibly,
-    // we would deserialize into a PortfolioV1 struct, map it to Portfolio (V2), and save.
-    // Here we simulate the schema evolution by populating the new `migration_time` field.
-    let mut portfolio: Portfolio = env
-        .storage()
-        .instance()
-        .get(&())
-        .unwrap_or_else(|| Portfolio::new(env));
+    // V1 storage is deserialized as `PortfolioV1`, mapped to the current `Portfolio` and
+    // saved below, since every field added since V1 changes the stored layout.
+    let had_v1_data = env.storage().instance().has(&());
+    let mut portfolio = load_portfolio_for_migration(env);
+    if had_v1_data {
+        env.storage().instance().set(&(), &portfolio);
+    }
+
+    // Keep the outgoing version's metrics so operators can check the migration preserved them.
+    // Unversioned storage predates versioning and is V1 data.
+    snapshot_metrics(env, current_version.max(1), &portfolio);
 
     // Update the data structure: Set migration timestamp if it wasn't set (simulating V2 feature)
     if portfolio.migration_time.is_none() {
@@ -28,9 +126,77 @@ pub fn migrate_from_v1_to_v2(env: &Env) -> Result<(), u32> {
         env.storage().instance().set(&(), &portfolio);
     }
 
-    // 3. Update version to 2
-    set_stored_version(env, 2);
+    // Badge variants added since V1 (VeteranTrader, MasterTrader, LuckyDraw, EarlyAdopter) are
+    // appended to the enum, so stored badge keys still decode; the struct itself was rewritten
+    // above. Traders who already passed the new trade-count thresholds get those badges now.
+    // EarlyAdopter is backfilled when the cutoff is set.
+    if portfolio.has_pending_trade_badges(env) {
+        portfolio.backfill_trade_badges(env);
+        env.storage().instance().set(&(), &portfolio);
+    }
 
+    // 3. Update version to the current one (2)
+    set_stored_version(env, CONTRACT_VERSION);
+    snapshot_metrics(env, CONTRACT_VERSION, &portfolio);
+
+    Ok(())
+}
+
+/// List the steps `migrate_from_v1_to_v2` would perform from the current state, in order.
+/// Read-only; empty once the stored version is current.
+pub fn describe_migration(env: &Env) -> Vec<Symbol> {
+    let mut steps = Vec::new(env);
+    if get_stored_version(env) >= CONTRACT_VERSION {
+        return steps;
+    }
+
+    if env.storage().instance().has(&()) {
+        steps.push_back(Symbol::new(env, "convert_v1"));
+    }
+    let portfolio = load_portfolio_for_migration(env);
+    if portfolio.migration_time.is_none() {
+        steps.push_back(Symbol::new(env, "backfill_tx_time"));
+    }
+    if portfolio.has_pending_trade_badges(env) {
+        steps.push_back(Symbol::new(env, "backfill_badges"));
+    }
+    steps.push_back(Symbol::new(env, "bump_version"));
+    steps
+}
+
+/// Record the portfolio metrics as of `version`
+fn snapshot_metrics(env: &Env, version: u32, portfolio: &Portfolio) {
+    env.storage()
+        .instance()
+        .set(&MigrationKey::MetricsSnapshot(version), &portfolio.get_metrics());
+}
+
+/// Metrics recorded when the contract moved to or away from `version`, if any
+pub fn get_metrics_snapshot(env: &Env, version: u32) -> Option<Metrics> {
+    env.storage().instance().get(&MigrationKey::MetricsSnapshot(version))
+}
+
+/// Build version info from the code version and the stored version
+pub fn get_version_info(env: &Env) -> VersionInfo {
+    let stored_version = get_stored_version(env);
+    VersionInfo {
+        major: CONTRACT_VERSION,
+        minor: CONTRACT_VERSION_MINOR,
+        stored_version,
+        migration_pending: migration_required(env),
+    }
+}
+
+/// True while the stored version is behind the code version
+pub fn migration_required(env: &Env) -> bool {
+    get_stored_version(env) < CONTRACT_VERSION
+}
+
+/// Reject with `MigrationRequired` until storage has been migrated
+pub fn require_migrated(env: &Env) -> Result<(), SwapTradeError> {
+    if migration_required(env) {
+        return Err(SwapTradeError::MigrationRequired);
+    }
     Ok(())
 }
 
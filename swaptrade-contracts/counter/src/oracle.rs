@@ -10,6 +10,9 @@ pub enum ContractError {
     PriceNotSet = 4,
 }
 
+/// Prices older than this are stale
+pub const STALE_THRESHOLD_SECONDS: u64 = 600; // 10 minutes
+
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct PriceData {
@@ -17,6 +20,29 @@ pub struct PriceData {
     pub timestamp: u64,
 }
 
+/// Confidence assumed when a price is stored without one
+pub const FULL_CONFIDENCE_BPS: u32 = 10000;
+
+/// Swaps are rejected when the oracle's confidence is below this
+pub const MIN_CONFIDENCE_BPS: u32 = 9000;
+
+/// A price read through `get_price_safe`; `stale` is set when the price is past the
+/// staleness threshold but still inside the grace window
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceQuote {
+    pub price: u128,
+    pub stale: bool,
+}
+
+#[contracttype]
+pub enum OracleKey {
+    GraceWindow,
+    /// Feed confidence in bps for a pair's price (10000 = fully confident). Kept apart from
+    /// `PriceData` so prices stored before confidence existed still decode.
+    Confidence(Symbol, Symbol),
+}
+
 pub trait PriceFeed {
     fn get_price(env: &Env, token_pair: (Symbol, Symbol)) -> Result<u128, ContractError>;
     fn last_update_time(env: &Env, token_pair: (Symbol, Symbol)) -> u64;
@@ -28,15 +54,48 @@ pub fn get_stored_price(env: &Env, pair: (Symbol, Symbol)) -> Option<PriceData>
     env.storage().instance().get(&pair)
 }
 
-pub fn set_stored_price(env: &Env, pair: (Symbol, Symbol), price: u128) {
+pub fn set_stored_price(env: &Env, pair: (Symbol, Symbol), price: u128, confidence: Option<u32>) {
+    let confidence = confidence.unwrap_or(FULL_CONFIDENCE_BPS);
+    assert!(confidence <= FULL_CONFIDENCE_BPS, "Confidence must be <= 10000 bps");
     let timestamp = env.ledger().timestamp();
     let data = PriceData { price, timestamp };
+    env.storage().instance().set(&OracleKey::Confidence(pair.0.clone(), pair.1.clone()), &confidence);
     env.storage().instance().set(&pair, &data);
 }
 
-pub fn get_price_safe(env: &Env, pair: (Symbol, Symbol)) -> Result<u128, ContractError> {
-    match get_stored_price(env, pair) {
-        Some(data) => Ok(data.price),
-        None => Err(ContractError::PriceNotSet),
+/// Stored price and confidence for a pair, if one has been set. Prices stored without a
+/// confidence are fully confident.
+pub fn get_price_with_confidence(env: &Env, pair: (Symbol, Symbol)) -> Option<(u128, u32)> {
+    let data = get_stored_price(env, pair.clone())?;
+    let confidence = env
+        .storage()
+        .instance()
+        .get(&OracleKey::Confidence(pair.0, pair.1))
+        .unwrap_or(FULL_CONFIDENCE_BPS);
+    Some((data.price, confidence))
+}
+
+/// Seconds beyond the staleness threshold during which the last price is still served (flagged stale)
+pub fn get_grace_window(env: &Env) -> u64 {
+    env.storage().instance().get(&OracleKey::GraceWindow).unwrap_or(0)
+}
+
+pub fn set_grace_window(env: &Env, seconds: u64) {
+    env.storage().instance().set(&OracleKey::GraceWindow, &seconds);
+}
+
+/// Fresh prices are returned as-is; prices inside the grace window are returned with
+/// `stale: true` so callers can decide; anything older fails with `StalePrice`.
+/// The grace window defaults to 0, i.e. no fallback.
+pub fn get_price_safe(env: &Env, pair: (Symbol, Symbol)) -> Result<PriceQuote, ContractError> {
+    let data = get_stored_price(env, pair).ok_or(ContractError::PriceNotSet)?;
+    let age = env.ledger().timestamp().saturating_sub(data.timestamp);
+
+    if age <= STALE_THRESHOLD_SECONDS {
+        Ok(PriceQuote { price: data.price, stale: false })
+    } else if age <= STALE_THRESHOLD_SECONDS.saturating_add(get_grace_window(env)) {
+        Ok(PriceQuote { price: data.price, stale: true })
+    } else {
+        Err(ContractError::StalePrice)
     }
 }
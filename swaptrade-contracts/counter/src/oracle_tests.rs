@@ -1,6 +1,7 @@
 #![cfg(test)]
 
 use super::*;
+use crate::admin_tests::setup_admin;
 use soroban_sdk::{symbol_short, Address, Env};
 use soroban_sdk::testutils::{Address as _, Ledger as _};
 
@@ -58,41 +59,57 @@ fn test_slippage_calculation() {
 }
 
 #[test]
-#[should_panic(expected = "Slippage exceeded")]
 fn test_max_slippage_enforcement() {
     let env = Env::default();
     let contract_id = env.register(CounterContract, ());
     let client = CounterContractClient::new(&env, &contract_id);
 
+    let lp = Address::generate(&env);
     let user = Address::generate(&env);
     let xlm = symbol_short!("XLM");
     let usdc = symbol_short!("USDCSIM");
 
-    client.set_price(&(xlm.clone(), usdc.clone()), &PRECISION);
+    client.mint(&xlm, &lp, &1000);
+    client.mint(&usdc, &lp, &1000);
+    client.add_liquidity(&1000, &1000, &lp);
     client.mint(&xlm, &user, &1000);
-    client.set_pool_liquidity(&usdc, &1000);
-    
-    // Set Max Slippage to 5% (500 bps)
-    client.set_max_slippage_bps(&500);
-    
-    // Swap 100 XLM -> 10% slippage -> Should Fail
-    client.swap(&xlm, &usdc, &100, &user);
+
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), PRECISION, None);
+        // Set Max Slippage to 5% (500 bps)
+        env.storage().instance().set(&symbol_short!("MAX_SLIP"), &500u32);
+
+        // Swap 100 XLM -> ~10% slippage -> Should Fail
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(result, Err(SwapTradeError::SlippageExceeded));
+
+        // try_swap records the same rejection instead of panicking
+        let out = CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(out, 0);
+    });
+    assert_eq!(client.balance_of(&xlm, &user), 1000);
+    assert_eq!(client.get_user_failed_swaps(&user, &1).get(0).unwrap().reason, OrderFailure::SlippageExceeded);
 }
 
 #[test]
-#[should_panic(expected = "Oracle price is stale")]
 fn test_stale_price() {
     let env = Env::default();
     
     let contract_id = env.register(CounterContract, ());
     let client = CounterContractClient::new(&env, &contract_id);
     
+    let lp = Address::generate(&env);
     let xlm = symbol_short!("XLM");
     let usdc = symbol_short!("USDCSIM");
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
     
     // Set price at t=0
     env.ledger().set_timestamp(0);
-    client.set_price(&(xlm.clone(), usdc.clone()), &PRECISION);
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), PRECISION, None);
+    });
     
     // Advance time beyond threshold (600s)
     env.ledger().set_timestamp(601);
@@ -101,7 +118,15 @@ fn test_stale_price() {
     client.mint(&xlm, &user, &100);
     
     // Swap should fail due to stale price
-    client.swap(&xlm, &usdc, &10, &user);
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 10, user.clone());
+        assert_eq!(result, Err(SwapTradeError::StalePrice));
+
+        let out = CounterContract::try_swap(env.clone(), xlm.clone(), usdc.clone(), 10, user.clone());
+        assert_eq!(out, 0);
+    });
+    assert_eq!(client.balance_of(&xlm, &user), 100);
+    assert_eq!(client.get_user_failed_swaps(&user, &1).get(0).unwrap().reason, OrderFailure::StalePrice);
 }
 
 #[test]
@@ -135,3 +160,262 @@ fn test_price_impact_on_pool() {
     let out_b = client.swap(&xlm, &usdc, &200, &user);
     assert_eq!(out_b, 153); // Confirms slippage increases as pool depletes
 }
+
+#[test]
+fn test_price_grace_window_flags_stale_then_errors() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    setup_admin(&env, &contract_id);
+    client.set_price_grace_window(&300);
+    assert_eq!(client.get_price_grace_window(), 300);
+
+    let pair = (symbol_short!("XLM"), symbol_short!("USDCSIM"));
+    env.ledger().set_timestamp(1_000);
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, pair.clone(), PRECISION, None);
+    });
+
+    // Fresh
+    env.ledger().set_timestamp(1_600);
+    env.as_contract(&contract_id, || {
+        let quote = oracle::get_price_safe(&env, pair.clone()).unwrap();
+        assert_eq!(quote, oracle::PriceQuote { price: PRECISION, stale: false });
+    });
+
+    // Inside the grace window
+    env.ledger().set_timestamp(1_900);
+    env.as_contract(&contract_id, || {
+        let quote = oracle::get_price_safe(&env, pair.clone()).unwrap();
+        assert_eq!(quote, oracle::PriceQuote { price: PRECISION, stale: true });
+    });
+
+    // Beyond the grace window
+    env.ledger().set_timestamp(1_901);
+    env.as_contract(&contract_id, || {
+        assert_eq!(
+            oracle::get_price_safe(&env, pair.clone()),
+            Err(oracle::ContractError::StalePrice)
+        );
+    });
+}
+
+#[test]
+fn test_low_confidence_price_rejects_swap() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+    client.mint(&xlm, &user, &1_000);
+
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), PRECISION, Some(5000));
+    });
+    assert_eq!(client.get_price_with_confidence(), (PRECISION, 5000));
+
+    env.as_contract(&contract_id, || {
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert_eq!(result, Err(SwapTradeError::LowConfidence));
+    });
+
+    // A confident price lets the same swap through
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), PRECISION, None);
+        let result = CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 100, user.clone());
+        assert!(result.is_ok());
+    });
+}
+
+/// The confidence check covers swaps against the direction the price is stored in
+#[test]
+fn test_low_confidence_price_rejects_reverse_swap() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+    client.mint(&usdc, &user, &1_000);
+
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), PRECISION, Some(5000));
+        let result = CounterContract::swap(env.clone(), usdc.clone(), xlm.clone(), 100, user.clone());
+        assert_eq!(result, Err(SwapTradeError::LowConfidence));
+    });
+    assert_eq!(client.balance_of(&usdc, &user), 1_000);
+}
+
+/// A price written before confidence was tracked still decodes and reads as fully confident
+#[test]
+fn test_price_stored_without_confidence_is_fully_confident() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.as_contract(&contract_id, || {
+        let legacy = oracle::PriceData { price: PRECISION, timestamp: 0 };
+        env.storage().instance().set(&(xlm.clone(), usdc.clone()), &legacy);
+    });
+    assert_eq!(client.get_price_with_confidence(), (PRECISION, oracle::FULL_CONFIDENCE_BPS));
+}
+
+/// The confidence endpoint reads a price stored as stable/XLM, inverted
+#[test]
+fn test_price_with_confidence_reads_reverse_pair() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.as_contract(&contract_id, || {
+        oracle::set_stored_price(&env, (usdc.clone(), xlm.clone()), 2 * PRECISION, Some(9500));
+    });
+    assert_eq!(client.get_price_with_confidence(), (PRECISION / 2, 9500));
+}
+
+/// Swap 1_000 XLM against a 100_000 / 100_000 pool with the oracle quoting `oracle_price`
+fn priced_swap_out(mode: PricingMode, oracle_price: Option<u128>) -> i128 {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.as_contract(&contract_id, || {
+        trading::set_pricing_mode(&env, mode);
+        if let Some(price) = oracle_price {
+            oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), price, None);
+        }
+
+        let mut portfolio = Portfolio::new(&env);
+        portfolio.add_pool_liquidity(100_000, 100_000);
+        portfolio.mint(&env, Asset::XLM, user.clone(), 10_000);
+        trading::perform_swap(&env, &mut portfolio, xlm.clone(), usdc.clone(), 1_000, user.clone())
+    })
+}
+
+#[test]
+fn test_pricing_mode_reserves_uses_pool() {
+    // 997 after fee: 100_000 * 997 / 100_997, ignoring the oracle's 2.0
+    assert_eq!(priced_swap_out(PricingMode::Reserves, Some(2 * PRECISION)), 987);
+}
+
+#[test]
+fn test_pricing_mode_oracle_uses_price() {
+    // 997 after fee at 0.5
+    assert_eq!(priced_swap_out(PricingMode::Oracle, Some(PRECISION / 2)), 498);
+}
+
+#[test]
+fn test_pricing_mode_oracle_capped_at_reserve_quote() {
+    // 1994 at 2.0 would shrink k; the reserve quote is paid instead
+    assert_eq!(priced_swap_out(PricingMode::Oracle, Some(2 * PRECISION)), 987);
+}
+
+#[test]
+#[should_panic(expected = "Oracle price unavailable")]
+fn test_pricing_mode_oracle_requires_price() {
+    priced_swap_out(PricingMode::Oracle, None);
+}
+
+#[test]
+fn test_pricing_mode_best_of_never_beats_reserve_quote() {
+    assert_eq!(priced_swap_out(PricingMode::BestOf, Some(2 * PRECISION)), 987);
+    assert_eq!(priced_swap_out(PricingMode::BestOf, Some(PRECISION / 2)), 987);
+    assert_eq!(priced_swap_out(PricingMode::BestOf, None), 987);
+}
+
+#[test]
+#[should_panic(expected = "Oracle price unavailable")]
+fn test_swap_oracle_max_age_stricter_than_general_staleness() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    env.ledger().set_timestamp(1_000);
+    env.as_contract(&contract_id, || {
+        trading::set_pricing_mode(&env, PricingMode::Oracle);
+        trading::set_swap_oracle_max_age(&env, 120);
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), 2 * PRECISION, None);
+    });
+
+    // Five minutes later the price is fine for a general read but too old to swap against
+    env.ledger().set_timestamp(1_300);
+    env.as_contract(&contract_id, || {
+        let quote = oracle::get_price_safe(&env, (xlm.clone(), usdc.clone())).unwrap();
+        assert!(!quote.stale);
+
+        let mut portfolio = Portfolio::new(&env);
+        portfolio.add_pool_liquidity(100_000, 100_000);
+        portfolio.mint(&env, Asset::XLM, user.clone(), 10_000);
+        trading::perform_swap(&env, &mut portfolio, xlm.clone(), usdc.clone(), 1_000, user.clone());
+    });
+}
+
+#[test]
+fn test_oracle_output_capped_by_reserves() {
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    let lp = Address::generate(&env);
+    let user = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &lp, &100_000);
+    client.mint(&usdc, &lp, &100_000);
+    client.add_liquidity(&100_000, &100_000, &lp);
+    client.mint(&xlm, &user, &1_000_000_000);
+
+    // At 2.0 the oracle would pay out far more than the 100_000 USDCSIM reserve;
+    // the constant-product quote caps it and the reserve is never emptied
+    let out = env.as_contract(&contract_id, || {
+        trading::set_pricing_mode(&env, PricingMode::Oracle);
+        oracle::set_stored_price(&env, (xlm.clone(), usdc.clone()), 2 * PRECISION, None);
+        trading::set_user_slippage_bps(&env, &user, 10_000);
+
+        CounterContract::swap(env.clone(), xlm.clone(), usdc.clone(), 1_000_000_000, user.clone()).unwrap()
+    });
+
+    let (xlm_reserve, usdc_reserve) = client.get_pair_reserves(&xlm, &usdc);
+    assert!(out < 100_000);
+    assert_eq!(usdc_reserve, 100_000 - out);
+    assert!(xlm_reserve.saturating_mul(usdc_reserve) >= 100_000 * 100_000);
+}
+
+#[test]
+fn test_set_pricing_mode_requires_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+
+    assert_eq!(client.get_pricing_mode(), PricingMode::Reserves);
+    assert_eq!(client.try_set_pricing_mode(&PricingMode::Oracle), Err(Ok(SwapTradeError::NotAdmin)));
+
+    setup_admin(&env, &contract_id);
+    client.set_pricing_mode(&PricingMode::BestOf);
+    assert_eq!(client.get_pricing_mode(), PricingMode::BestOf);
+}
@@ -0,0 +1,31 @@
+use soroban_sdk::{symbol_short, xdr::ToXdr, Address, Bytes, Env};
+
+/// Deterministic pseudo-random u64 for `user` at the current ledger. The same ledger sequence,
+/// user and `nonce` always produce the same value. Predictable to anyone who can choose when to
+/// submit, so only suitable for low-stakes rewards.
+pub fn next_u64(env: &Env, user: &Address, nonce: u64) -> u64 {
+    let mut seed = Bytes::new(env);
+    seed.extend_from_array(&env.ledger().sequence().to_be_bytes());
+    seed.append(&user.clone().to_xdr(env));
+    seed.extend_from_array(&nonce.to_be_bytes());
+
+    let digest = env.crypto().sha256(&seed).to_array();
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(word)
+}
+
+/// True with probability `bps` / 10000
+pub fn roll_bps(env: &Env, user: &Address, nonce: u64, bps: u32) -> bool {
+    next_u64(env, user, nonce) % 10000 < bps as u64
+}
+
+/// Chance (bps) that `maybe_award_bonus` grants the bonus badge (0 = never)
+pub fn get_bonus_probability_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&symbol_short!("BONUS_P")).unwrap_or(0)
+}
+
+pub fn set_bonus_probability_bps(env: &Env, bps: u32) {
+    assert!(bps <= 10000, "Probability must be <= 10000 bps");
+    env.storage().instance().set(&symbol_short!("BONUS_P"), &bps);
+}
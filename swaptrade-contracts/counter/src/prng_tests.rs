@@ -0,0 +1,62 @@
+#![cfg(test)]
+
+use super::*;
+use crate::admin_tests::setup_admin;
+use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, Env};
+
+#[test]
+fn test_prng_is_deterministic() {
+    let env = Env::default();
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    env.ledger().set_sequence_number(42);
+    let first = prng::next_u64(&env, &user, 0);
+    assert_eq!(prng::next_u64(&env, &user, 0), first);
+
+    // Any change of input changes the output
+    assert_ne!(prng::next_u64(&env, &user, 1), first);
+    assert_ne!(prng::next_u64(&env, &other, 0), first);
+    env.ledger().set_sequence_number(43);
+    assert_ne!(prng::next_u64(&env, &user, 0), first);
+}
+
+#[test]
+fn test_prng_roll_distribution() {
+    let env = Env::default();
+    let user = Address::generate(&env);
+
+    // A 25% roll over 2000 draws lands well inside 20%..30%
+    let hits = (0..2000u64).filter(|nonce| prng::roll_bps(&env, &user, *nonce, 2500)).count();
+    assert!(hits > 400 && hits < 600, "hits = {}", hits);
+
+    assert!(!(0..100u64).any(|nonce| prng::roll_bps(&env, &user, nonce, 0)));
+    assert!((0..100u64).all(|nonce| prng::roll_bps(&env, &user, nonce, 10000)));
+}
+
+#[test]
+fn test_maybe_award_bonus_respects_probability() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = Address::generate(&env);
+
+    // Probability defaults to zero
+    assert!(!client.maybe_award_bonus(&user));
+
+    setup_admin(&env, &contract_id);
+    client.set_bonus_probability_bps(&10000);
+
+    assert!(client.maybe_award_bonus(&user));
+    assert!(client.has_badge(&user, &Badge::LuckyDraw));
+
+    // Already holding the badge, a second win awards nothing new
+    assert!(!client.maybe_award_bonus(&user));
+
+    // Blacklisted users can't enter the draw
+    let banned = Address::generate(&env);
+    client.blacklist_user(&banned);
+    assert_eq!(client.try_maybe_award_bonus(&banned), Err(Ok(SwapTradeError::Blacklisted)));
+    assert!(!client.has_badge(&banned, &Badge::LuckyDraw));
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, Env, Map, symbol_short};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol, Vec, symbol_short};
 use crate::tiers::UserTier;
 
 /// Rate limit configuration per tier
@@ -88,10 +88,67 @@ impl TimeWindow {
     }
 }
 
+/// How operations are counted against a limit
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitMode {
+    /// Counters reset at fixed hour/day boundaries (allows bursts across a boundary)
+    Fixed,
+    /// Counts operations in the trailing window ending now
+    Sliding,
+}
+
 /// Rate limiter for swap and LP operations
 pub struct RateLimiter;
 
 impl RateLimiter {
+    /// Current counting mode (defaults to fixed windows)
+    pub fn get_mode(env: &Env) -> RateLimitMode {
+        env.storage()
+            .persistent()
+            .get(&symbol_short!("rl_mode"))
+            .unwrap_or(RateLimitMode::Fixed)
+    }
+
+    /// Switch the counting mode. Operations are recorded for both modes whichever is active,
+    /// so a switch mid-window carries each user's current usage over.
+    pub fn set_mode(env: &Env, mode: RateLimitMode) {
+        env.storage().persistent().set(&symbol_short!("rl_mode"), &mode);
+    }
+
+    /// Timestamps of the user's `kind` operations inside the trailing window ending at `now`
+    fn recent_ops(env: &Env, user: &Address, kind: Symbol, now: u64, duration: u64) -> Vec<u64> {
+        let history: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&(user.clone(), kind))
+            .unwrap_or(Vec::new(env));
+
+        let mut recent = Vec::new(env);
+        for ts in history.iter() {
+            if ts + duration > now {
+                recent.push_back(ts);
+            }
+        }
+        recent
+    }
+
+    /// Sliding-window usage: (count, milliseconds until the oldest op leaves the window)
+    fn sliding_usage(env: &Env, user: &Address, kind: Symbol, now: u64, duration: u64) -> (u32, u64) {
+        let recent = Self::recent_ops(env, user, kind, now, duration);
+        let cooldown_ms = match recent.get(0) {
+            Some(oldest) => (oldest + duration).saturating_sub(now) * 1000,
+            None => 0,
+        };
+        (recent.len(), cooldown_ms)
+    }
+
+    fn record_sliding(env: &Env, user: &Address, kind: Symbol, timestamp: u64, duration: u64) {
+        let mut recent = Self::recent_ops(env, user, kind.clone(), timestamp, duration);
+        recent.push_back(timestamp);
+        env.storage().persistent().set(&(user.clone(), kind), &recent);
+    }
+
     /// Check and record a swap operation for the user
     /// Returns Ok(()) if operation is allowed, Err with cooldown if rate limited
     pub fn check_swap_limit(
@@ -108,19 +165,84 @@ impl RateLimiter {
 
         let timestamp = env.ledger().timestamp();
         let window = TimeWindow::hourly(timestamp);
-        let count_key = (user.clone(), symbol_short!("swap"), window.window_start);
 
-        // Get current count
-        let current_count: u32 = env
-            .storage()
-            .persistent()
-            .get(&count_key)
-            .unwrap_or(0);
+        let (current_count, cooldown_ms) = match Self::get_mode(env) {
+            RateLimitMode::Sliding => {
+                Self::sliding_usage(env, user, symbol_short!("swap_ts"), timestamp, window.window_duration)
+            }
+            RateLimitMode::Fixed => {
+                let count_key = (user.clone(), symbol_short!("swap"), window.window_start);
+                let count: u32 = env
+                    .storage()
+                    .persistent()
+                    .get(&count_key)
+                    .unwrap_or(0);
+                (count, window.cooldown_ms(timestamp))
+            }
+        };
 
         if current_count >= config.swaps_per_hour {
             return Err(RateLimitStatus {
                 used: current_count,
                 limit: config.swaps_per_hour,
+                cooldown_ms,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Order-independent key for a trading pair, so XLM/USDC and USDC/XLM share a bucket
+    pub(crate) fn canonical_pair(from: &Symbol, to: &Symbol) -> (Symbol, Symbol) {
+        if from <= to {
+            (from.clone(), to.clone())
+        } else {
+            (to.clone(), from.clone())
+        }
+    }
+
+    /// Per-user hourly swap limit for a pair, if the admin has configured one
+    pub fn get_pair_swap_limit(env: &Env, from: &Symbol, to: &Symbol) -> Option<u32> {
+        env.storage()
+            .persistent()
+            .get(&(symbol_short!("pair_lim"), Self::canonical_pair(from, to)))
+    }
+
+    /// Set the per-user hourly swap limit for a pair; 0 removes it
+    pub fn set_pair_swap_limit(env: &Env, from: &Symbol, to: &Symbol, swaps_per_hour: u32) {
+        let key = (symbol_short!("pair_lim"), Self::canonical_pair(from, to));
+        if swaps_per_hour == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &swaps_per_hour);
+        }
+    }
+
+    /// Check a swap on `from`/`to`: the user's tier limit first, then the pair's own
+    /// bucket when one is configured. Pair buckets always use fixed hourly windows.
+    pub fn check_pair_swap_limit(
+        env: &Env,
+        user: &Address,
+        tier: &UserTier,
+        from: &Symbol,
+        to: &Symbol,
+    ) -> Result<(), RateLimitStatus> {
+        Self::check_swap_limit(env, user, tier)?;
+
+        let limit = match Self::get_pair_swap_limit(env, from, to) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let timestamp = env.ledger().timestamp();
+        let window = TimeWindow::hourly(timestamp);
+        let count_key = (user.clone(), Self::canonical_pair(from, to), window.window_start);
+        let used: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+
+        if used >= limit {
+            return Err(RateLimitStatus {
+                used,
+                limit,
                 cooldown_ms: window.cooldown_ms(timestamp),
             });
         }
@@ -128,9 +250,29 @@ impl RateLimiter {
         Ok(())
     }
 
-    /// Record a swap operation in storage
+    /// Record a swap against the pair's bucket, if the pair is limited
+    pub fn record_pair_swap(env: &Env, user: &Address, from: &Symbol, to: &Symbol, timestamp: u64) {
+        if Self::get_pair_swap_limit(env, from, to).is_none() {
+            return;
+        }
+        let window = TimeWindow::hourly(timestamp);
+        let count_key = (user.clone(), Self::canonical_pair(from, to), window.window_start);
+
+        let current_count: u32 = env
+            .storage()
+            .persistent()
+            .get(&count_key)
+            .unwrap_or(0);
+
+        env.storage()
+            .persistent()
+            .set(&count_key, &(current_count + 1));
+    }
+
+    /// Record a swap operation in storage, for both counting modes
     pub fn record_swap(env: &Env, user: &Address, timestamp: u64) {
         let window = TimeWindow::hourly(timestamp);
+        Self::record_sliding(env, user, symbol_short!("swap_ts"), timestamp, window.window_duration);
         let count_key = (user.clone(), symbol_short!("swap"), window.window_start);
 
         let current_count: u32 = env
@@ -159,28 +301,37 @@ impl RateLimiter {
 
         let timestamp = env.ledger().timestamp();
         let window = TimeWindow::daily(timestamp);
-        let count_key = (user.clone(), symbol_short!("lp_op"), window.window_start);
 
-        let current_count: u32 = env
-            .storage()
-            .persistent()
-            .get(&count_key)
-            .unwrap_or(0);
+        let (current_count, cooldown_ms) = match Self::get_mode(env) {
+            RateLimitMode::Sliding => {
+                Self::sliding_usage(env, user, symbol_short!("lp_ts"), timestamp, window.window_duration)
+            }
+            RateLimitMode::Fixed => {
+                let count_key = (user.clone(), symbol_short!("lp_op"), window.window_start);
+                let count: u32 = env
+                    .storage()
+                    .persistent()
+                    .get(&count_key)
+                    .unwrap_or(0);
+                (count, window.cooldown_ms(timestamp))
+            }
+        };
 
         if current_count >= config.lp_ops_per_day {
             return Err(RateLimitStatus {
                 used: current_count,
                 limit: config.lp_ops_per_day,
-                cooldown_ms: window.cooldown_ms(timestamp),
+                cooldown_ms,
             });
         }
 
         Ok(())
     }
 
-    /// Record an LP operation in storage
+    /// Record an LP operation in storage, for both counting modes
     pub fn record_lp_op(env: &Env, user: &Address, timestamp: u64) {
         let window = TimeWindow::daily(timestamp);
+        Self::record_sliding(env, user, symbol_short!("lp_ts"), timestamp, window.window_duration);
         let count_key = (user.clone(), symbol_short!("lp_op"), window.window_start);
 
         let current_count: u32 = env
@@ -203,6 +354,13 @@ impl RateLimiter {
         let config = RateLimitConfig::for_tier(tier);
         let timestamp = env.ledger().timestamp();
         let window = TimeWindow::hourly(timestamp);
+
+        if Self::get_mode(env) == RateLimitMode::Sliding {
+            let (used, cooldown_ms) =
+                Self::sliding_usage(env, user, symbol_short!("swap_ts"), timestamp, window.window_duration);
+            return RateLimitStatus { used, limit: config.swaps_per_hour, cooldown_ms };
+        }
+
         let count_key = (user.clone(), symbol_short!("swap"), window.window_start);
 
         let used: u32 = env
@@ -227,6 +385,13 @@ impl RateLimiter {
         let config = RateLimitConfig::for_tier(tier);
         let timestamp = env.ledger().timestamp();
         let window = TimeWindow::daily(timestamp);
+
+        if Self::get_mode(env) == RateLimitMode::Sliding {
+            let (used, cooldown_ms) =
+                Self::sliding_usage(env, user, symbol_short!("lp_ts"), timestamp, window.window_duration);
+            return RateLimitStatus { used, limit: config.lp_ops_per_day, cooldown_ms };
+        }
+
         let count_key = (user.clone(), symbol_short!("lp_op"), window.window_start);
 
         let used: u32 = env
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod rate_limit_tests {
-    use crate::{CounterContract, RateLimiter, UserTier};
+    use crate::{CounterContract, RateLimitMode, RateLimiter, UserTier};
     use soroban_sdk::{testutils::{self, Address as _, Ledger}, Address, Env, Symbol, symbol_short};
 
     fn create_test_env() -> (Env, Address) {
@@ -306,4 +306,136 @@ mod rate_limit_tests {
         let check = RateLimiter::check_swap_limit(&env, &user, &trader);
         assert!(check.is_err());
     }
+
+    #[test]
+    fn test_batched_swap_rate_limits_preserve_order() {
+        let env = Env::default();
+        let contract_id = env.register(CounterContract, ());
+        let client = crate::CounterContractClient::new(&env, &contract_id);
+        let user1 = Address::generate(&env);
+        let user2 = Address::generate(&env);
+        let user3 = Address::generate(&env);
+
+        // Give each user a different amount of usage in the current hour
+        env.ledger().set_timestamp(3600);
+        env.as_contract(&contract_id, || {
+            RateLimiter::record_swap(&env, &user1, 3600);
+            for _ in 0..3 {
+                RateLimiter::record_swap(&env, &user3, 3600);
+            }
+        });
+
+        let mut users = soroban_sdk::Vec::new(&env);
+        users.push_back(user1.clone());
+        users.push_back(user2.clone());
+        users.push_back(user3.clone());
+
+        let statuses = client.get_swap_rate_limits(&users);
+        assert_eq!(statuses.len(), 3);
+        assert_eq!(statuses.get(0).unwrap().used, 1);
+        assert_eq!(statuses.get(1).unwrap().used, 0);
+        assert_eq!(statuses.get(2).unwrap().used, 3);
+
+        // Each entry matches the single-user query
+        assert_eq!(statuses.get(2).unwrap().used, client.get_swap_rate_limit(&user3).used);
+    }
+
+    #[test]
+    fn test_sliding_window_throttles_boundary_burst() {
+        let env = Env::default();
+        let contract_id = env.register(CounterContract, ());
+        let fixed_user = Address::generate(&env);
+        let sliding_user = Address::generate(&env);
+        let novice = UserTier::Novice;
+
+        env.as_contract(&contract_id, || {
+            // Fixed windows: 5 swaps just before the hour boundary, then 5 more just after
+            env.ledger().set_timestamp(3590);
+            for _ in 0..5 {
+                assert!(RateLimiter::check_swap_limit(&env, &fixed_user, &novice).is_ok());
+                RateLimiter::record_swap(&env, &fixed_user, 3590);
+            }
+            env.ledger().set_timestamp(3600);
+            assert!(
+                RateLimiter::check_swap_limit(&env, &fixed_user, &novice).is_ok(),
+                "Fixed windows reset at the boundary"
+            );
+
+            // Sliding windows: the same burst is still counted after the boundary
+            RateLimiter::set_mode(&env, RateLimitMode::Sliding);
+            env.ledger().set_timestamp(3590);
+            for _ in 0..5 {
+                assert!(RateLimiter::check_swap_limit(&env, &sliding_user, &novice).is_ok());
+                RateLimiter::record_swap(&env, &sliding_user, 3590);
+            }
+            env.ledger().set_timestamp(3600);
+            let status = RateLimiter::check_swap_limit(&env, &sliding_user, &novice).unwrap_err();
+            assert_eq!(status.used, 5);
+            // Frees up once the burst is an hour old
+            assert_eq!(status.cooldown_ms, 3_590_000);
+
+            env.ledger().set_timestamp(3590 + 3600);
+            assert!(RateLimiter::check_swap_limit(&env, &sliding_user, &novice).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_mode_switch_keeps_current_usage() {
+        let env = Env::default();
+        let contract_id = env.register(CounterContract, ());
+        let user = Address::generate(&env);
+        let novice = UserTier::Novice;
+
+        env.as_contract(&contract_id, || {
+            // Use up the hourly allowance under fixed windows
+            env.ledger().set_timestamp(1800);
+            for _ in 0..5 {
+                assert!(RateLimiter::check_swap_limit(&env, &user, &novice).is_ok());
+                RateLimiter::record_swap(&env, &user, 1800);
+            }
+
+            // Switching to sliding windows mid-window doesn't hand out a fresh allowance
+            RateLimiter::set_mode(&env, RateLimitMode::Sliding);
+            let status = RateLimiter::check_swap_limit(&env, &user, &novice).unwrap_err();
+            assert_eq!(status.used, 5);
+
+            // Nor does switching back
+            RateLimiter::set_mode(&env, RateLimitMode::Fixed);
+            assert_eq!(RateLimiter::check_swap_limit(&env, &user, &novice).unwrap_err().used, 5);
+        });
+    }
+
+    #[test]
+    fn test_pair_limit_leaves_other_pairs_unrestricted() {
+        let env = Env::default();
+        let contract_id = env.register(CounterContract, ());
+        let user = Address::generate(&env);
+        let novice = UserTier::Novice;
+        let xlm = symbol_short!("XLM");
+        let usdc = symbol_short!("USDCSIM");
+        let btc = symbol_short!("BTC");
+
+        env.as_contract(&contract_id, || {
+            env.ledger().set_timestamp(3600);
+            RateLimiter::set_pair_swap_limit(&env, &xlm, &usdc, 2);
+
+            // Both directions draw from the same XLM/USDCSIM bucket
+            assert!(RateLimiter::check_pair_swap_limit(&env, &user, &novice, &xlm, &usdc).is_ok());
+            RateLimiter::record_pair_swap(&env, &user, &xlm, &usdc, 3600);
+            assert!(RateLimiter::check_pair_swap_limit(&env, &user, &novice, &usdc, &xlm).is_ok());
+            RateLimiter::record_pair_swap(&env, &user, &usdc, &xlm, 3600);
+
+            let status = RateLimiter::check_pair_swap_limit(&env, &user, &novice, &xlm, &usdc).unwrap_err();
+            assert_eq!(status.used, 2);
+            assert_eq!(status.limit, 2);
+
+            // Another pair only answers to the tier limit
+            assert_eq!(RateLimiter::get_pair_swap_limit(&env, &xlm, &btc), None);
+            assert!(RateLimiter::check_pair_swap_limit(&env, &user, &novice, &xlm, &btc).is_ok());
+
+            // The pair bucket resets with the next hour
+            env.ledger().set_timestamp(7200);
+            assert!(RateLimiter::check_pair_swap_limit(&env, &user, &novice, &xlm, &usdc).is_ok());
+        });
+    }
 }
@@ -2,3 +2,6 @@ use soroban_sdk::Symbol;
 
 pub const ADMIN_KEY: Symbol = Symbol::short("admin");
 pub const PAUSED_KEY: Symbol = Symbol::short("paused");
+pub const BLACKLIST_KEY: Symbol = Symbol::short("blacklist");
+pub const INTEGRATORS_KEY: Symbol = Symbol::short("integrtr");
+pub const DEPOSITS_FROZEN_KEY: Symbol = Symbol::short("dep_frz");
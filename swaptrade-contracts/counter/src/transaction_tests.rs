@@ -40,6 +40,30 @@ fn test_record_transaction_stores_data_correctly() {
     assert_eq!(tx.rate_achieved, 9_800_000);
 }
 
+#[test]
+fn test_rate_achieved_uses_pair_decimals() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let user = soroban_sdk::Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    // 1 / 3: 7 decimals by default, 12 once configured (either direction of the pair)
+    portfolio.record_transaction(&env, user.clone(), xlm.clone(), usdc.clone(), 3_000, 1_000);
+    portfolio.set_rate_decimals(&usdc, &xlm, 12);
+    assert_eq!(portfolio.get_rate_decimals(&xlm, &usdc), 12);
+    portfolio.record_transaction(&env, user.clone(), xlm.clone(), usdc.clone(), 3_000, 1_000);
+
+    let txs = portfolio.get_user_transactions(&env, user.clone(), 10);
+    assert_eq!(txs.get(0).unwrap().rate_achieved, 3_333_333);
+    assert_eq!(txs.get(1).unwrap().rate_achieved, 333_333_333_333);
+
+    // Other pairs keep the default, and the setting is capped
+    assert_eq!(portfolio.get_rate_decimals(&symbol_short!("A"), &symbol_short!("B")), 7);
+    portfolio.set_rate_decimals(&xlm, &usdc, 40);
+    assert_eq!(portfolio.get_rate_decimals(&xlm, &usdc), 18);
+}
+
 #[test]
 fn test_transaction_limit_capped_at_100() {
     let env = Env::default();
@@ -92,3 +116,195 @@ fn test_get_user_transactions_limit_works() {
     let limited = portfolio.get_user_transactions(&env, user.clone(), 5);
     assert_eq!(limited.len(), 5);
 }
+
+#[test]
+fn test_transaction_history_evicts_oldest_first() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let user = soroban_sdk::Address::generate(&env);
+    let cap = 20u32;
+    portfolio.set_tx_history_cap(cap);
+
+    // Record cap + 5 transactions with increasing amounts
+    for i in 0..(cap as i128 + 5) {
+        portfolio.record_transaction(
+            &env,
+            user.clone(),
+            symbol_short!("XLM"),
+            symbol_short!("USDCSIM"),
+            1 + i,
+            1 + i
+        );
+    }
+
+    let transactions = portfolio.get_user_transactions(&env, user.clone(), 1000);
+    assert_eq!(transactions.len(), cap);
+
+    // The 5 oldest (1..=5) are gone; the newest `cap` remain in order
+    for i in 0..cap {
+        let tx = transactions.get(i).unwrap();
+        assert_eq!(tx.from_amount, 6 + i as i128);
+    }
+}
+
+#[test]
+fn test_get_user_transactions_clamped_to_max_return() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let user = soroban_sdk::Address::generate(&env);
+
+    for i in 0..10 {
+        env.ledger().set_timestamp(100 + i);
+        portfolio.record_transaction(&env, user.clone(), symbol_short!("XLM"), symbol_short!("USDC"), 100, 98);
+    }
+
+    assert_eq!(portfolio.get_max_tx_return(), 100);
+    assert_eq!(portfolio.get_user_transactions(&env, user.clone(), 1_000).len(), 10);
+
+    portfolio.set_max_tx_return(4);
+    let txs = portfolio.get_user_transactions(&env, user.clone(), 1_000);
+    assert_eq!(txs.len(), 4);
+    // Still the most recent ones
+    assert_eq!(txs.get(3).unwrap().timestamp, 109);
+    assert_eq!(portfolio.get_user_transactions(&env, user.clone(), 2).len(), 2);
+}
+
+#[test]
+fn test_trades_in_window_counts_recent_only() {
+    let env = Env::default();
+    let mut portfolio = Portfolio::new(&env);
+    let user = soroban_sdk::Address::generate(&env);
+
+    for ts in [100u64, 500, 900, 950, 1000] {
+        env.ledger().set_timestamp(ts);
+        portfolio.record_transaction(&env, user.clone(), symbol_short!("XLM"), symbol_short!("USDC"), 100, 98);
+    }
+
+    // At t=1000, a 100s window covers t=900..=1000
+    assert_eq!(portfolio.get_user_trades_in_window(&env, user.clone(), 100), 3);
+    assert_eq!(portfolio.get_user_trades_in_window(&env, user.clone(), 0), 1);
+    assert_eq!(portfolio.get_user_trades_in_window(&env, user.clone(), 10_000), 5);
+
+    env.ledger().set_timestamp(5_000);
+    assert_eq!(portfolio.get_user_trades_in_window(&env, user.clone(), 100), 0);
+
+    let other = soroban_sdk::Address::generate(&env);
+    assert_eq!(portfolio.get_user_trades_in_window(&env, other, 10_000), 0);
+}
+
+#[test]
+fn test_activity_timeline_orders_mixed_events() {
+    use crate::{ActivityEvent, CounterContract, CounterContractClient};
+    use crate::portfolio::Badge;
+
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = soroban_sdk::Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDCSIM");
+
+    client.mint(&xlm, &user, &2_000_000);
+    client.mint(&usdc, &user, &2_000_000);
+
+    env.ledger().set_timestamp(100);
+    client.add_liquidity(&1_000_000, &1_000_000, &user);
+
+    env.ledger().set_timestamp(200);
+    client.swap(&xlm, &usdc, &1000, &user);
+
+    env.ledger().set_timestamp(300);
+    let (xlm_out, usdc_out) = client.remove_liquidity(&1000, &user);
+
+    let timeline = client.get_activity_timeline(&user, &10);
+    assert_eq!(timeline.len(), 5);
+    assert_eq!(timeline.get(0).unwrap(), ActivityEvent::LiquidityAdded(100, 1_000_000, 1_000_000));
+    assert_eq!(timeline.get(1).unwrap(), ActivityEvent::BadgeEarned(100, Badge::LiquidityProvider));
+    match timeline.get(2).unwrap() {
+        ActivityEvent::Trade(tx) => {
+            assert_eq!(tx.timestamp, 200);
+            assert_eq!(tx.from_token, xlm);
+            assert_eq!(tx.from_amount, 1000);
+        }
+        other => panic!("expected a trade, got {:?}", other),
+    }
+    assert_eq!(timeline.get(3).unwrap(), ActivityEvent::BadgeEarned(200, Badge::FirstTrade));
+    assert_eq!(timeline.get(4).unwrap(), ActivityEvent::LiquidityRemoved(300, xlm_out, usdc_out));
+
+    // A limit keeps the most recent events
+    let recent = client.get_activity_timeline(&user, &2);
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent.get(1).unwrap(), ActivityEvent::LiquidityRemoved(300, xlm_out, usdc_out));
+}
+
+#[test]
+fn test_badge_award_time_recorded() {
+    use crate::{CounterContract, CounterContractClient};
+    use crate::portfolio::Badge;
+
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = soroban_sdk::Address::generate(&env);
+
+    client.mint(&symbol_short!("XLM"), &user, &1000);
+    client.mint(&symbol_short!("USDCSIM"), &user, &1000);
+
+    env.ledger().set_timestamp(1_234);
+    client.add_liquidity(&1000, &1000, &user);
+
+    assert_eq!(client.get_badge_award_time(&user, &Badge::LiquidityProvider), Some(1_234));
+    assert_eq!(client.get_badge_award_time(&user, &Badge::FirstTrade), None);
+}
+
+#[test]
+fn test_get_badges_for_users_in_input_order() {
+    use crate::{CounterContract, CounterContractClient};
+    use crate::portfolio::Badge;
+
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let none = soroban_sdk::Address::generate(&env);
+    let one = soroban_sdk::Address::generate(&env);
+    let two = soroban_sdk::Address::generate(&env);
+
+    env.as_contract(&contract_id, || {
+        let mut portfolio = Portfolio::new(&env);
+        portfolio.award_badge(&env, one.clone(), Badge::FirstTrade);
+        portfolio.award_badge(&env, two.clone(), Badge::FirstTrade);
+        portfolio.award_badge(&env, two.clone(), Badge::LiquidityProvider);
+        env.storage().instance().set(&(), &portfolio);
+    });
+
+    let users = soroban_sdk::vec![&env, two.clone(), none.clone(), one.clone()];
+    let badges = client.get_badges_for_users(&users);
+
+    assert_eq!(badges.len(), 3);
+    assert_eq!(badges.get(0).unwrap(), client.get_user_badges(&two));
+    assert_eq!(badges.get(0).unwrap().len(), 2);
+    assert_eq!(badges.get(1).unwrap().len(), 0);
+    assert_eq!(badges.get(2).unwrap(), soroban_sdk::vec![&env, Badge::FirstTrade]);
+}
+
+#[test]
+fn test_first_activity_time_set_once() {
+    use crate::{CounterContract, CounterContractClient};
+
+    let env = Env::default();
+    let contract_id = env.register(CounterContract, ());
+    let client = CounterContractClient::new(&env, &contract_id);
+    let user = soroban_sdk::Address::generate(&env);
+
+    assert_eq!(client.get_first_activity_time(), None);
+    assert_eq!(client.get_activity_age(), 0);
+
+    env.ledger().set_timestamp(1_000);
+    client.mint(&symbol_short!("XLM"), &user, &100);
+    assert_eq!(client.get_first_activity_time(), Some(1_000));
+
+    env.ledger().set_timestamp(5_000);
+    client.mint(&symbol_short!("XLM"), &user, &100);
+    assert_eq!(client.get_first_activity_time(), Some(1_000));
+    assert_eq!(client.get_activity_age(), 4_000);
+}
@@ -1,37 +1,52 @@
-use soroban_sdk::{Env, Symbol, Address, symbol_short};
+use soroban_sdk::{contracttype, Env, Symbol, Address, Vec, symbol_short};
 // use crate::events::SwapExecuted;
-use crate::portfolio::{Portfolio, Asset};
-use crate::oracle::{get_stored_price, ContractError};
+use crate::portfolio::{Portfolio, Asset, OrderFailure, SECONDS_PER_DAY};
+use crate::assets;
+use crate::errors::SwapTradeError;
+use crate::tiers::UserTier;
+use crate::oracle::{self, get_stored_price, ContractError, STALE_THRESHOLD_SECONDS};
 
 const PRECISION: u128 = 1_000_000_000_000_000_000; // 1e18
-const STALE_THRESHOLD_SECONDS: u64 = 600; // 10 minutes
 const LP_FEE_BPS: u128 = 30; // 0.3% = 30 basis points
+/// Upper bound for integrator-supplied swap fees
+pub const MAX_FEE_BPS: u32 = 100; // 1%
+/// Slippage tolerance applied by plain swaps for users who haven't set their own.
+/// Wide enough for ordinary large trades; it only catches extreme price impact.
+pub const DEFAULT_SLIPPAGE_BPS: u32 = 3_000; // 30%
+pub const RATE_PRECISION: u128 = 10_000_000; // 1e7, same scale as transaction rates
+/// Ledgers a day's free-swap usage is kept for (one day of 5-second ledgers)
+const FREE_SWAP_TTL_LEDGERS: u32 = 17_280;
 
 
-fn symbol_to_asset(sym: &Symbol) -> Option<Asset> {
+fn symbol_to_asset(portfolio: &Portfolio, sym: &Symbol) -> Option<Asset> {
     if *sym == symbol_short!("XLM") {
         Some(Asset::XLM)
-    } else if *sym == symbol_short!("USDCSIM") {
+    } else if *sym == portfolio.stable_symbol() {
         Some(Asset::Custom(sym.clone()))
     } else {
         None
     }
 }
 
+/// Whether `from` -> `to` is a pair the pool trades: XLM and the stable asset, either way
+pub fn is_supported_pair(portfolio: &Portfolio, from: &Symbol, to: &Symbol) -> bool {
+    from != to && symbol_to_asset(portfolio, from).is_some() && symbol_to_asset(portfolio, to).is_some()
+}
+
 
 
-// Helper to get price with staleness check
-fn get_price_with_staleness_check(env: &Env, from: Symbol, to: Symbol) -> Result<u128, ContractError> {
+// Helper to get price with staleness check; prices older than `max_age` seconds are stale
+fn get_price_with_staleness_check(env: &Env, from: Symbol, to: Symbol, max_age: u64) -> Result<u128, ContractError> {
     // Try (from, to)
     if let Some(data) = get_stored_price(env, (from.clone(), to.clone())) {
-        if env.ledger().timestamp() - data.timestamp > STALE_THRESHOLD_SECONDS {
+        if env.ledger().timestamp() - data.timestamp > max_age {
              return Err(ContractError::StalePrice);
         }
         return Ok(data.price);
     }
     // Try (to, from) and invert
     if let Some(data) = get_stored_price(env, (to.clone(), from.clone())) {
-        if env.ledger().timestamp() - data.timestamp > STALE_THRESHOLD_SECONDS {
+        if env.ledger().timestamp() - data.timestamp > max_age {
              return Err(ContractError::StalePrice);
         }
         if data.price == 0 { return Err(ContractError::InvalidPrice); }
@@ -43,6 +58,503 @@ fn get_price_with_staleness_check(env: &Env, from: Symbol, to: Symbol) -> Result
     Err(ContractError::PriceNotSet)
 }
 
+/// Oracle price of `from` in `to` and its confidence, reading a price stored in either
+/// orientation (a reversed price is inverted). `None` if no price is set.
+pub fn price_with_confidence(env: &Env, from: &Symbol, to: &Symbol) -> Option<(u128, u32)> {
+    if let Some(quote) = oracle::get_price_with_confidence(env, (from.clone(), to.clone())) {
+        return Some(quote);
+    }
+    let (price, confidence) = oracle::get_price_with_confidence(env, (to.clone(), from.clone()))?;
+    let inv = if price == 0 { 0 } else { (PRECISION * PRECISION) / price };
+    Some((inv, confidence))
+}
+
+// Rounding policy: whenever integer division splits value between a user and the pool,
+// the remainder stays with the pool. Fees round up (toward the protocol) and amounts
+// paid out to users round down, so rounding can never drain the reserves.
+
+/// Fee of `fee_bps` on `amount`, rounded up toward the protocol
+pub fn fee_round_up(amount: i128, fee_bps: u32) -> i128 {
+    if amount <= 0 || fee_bps == 0 {
+        return 0;
+    }
+    let scaled = (amount as u128).saturating_mul(fee_bps as u128);
+    ((scaled + 9999) / 10000) as i128
+}
+
+/// `numerator / denominator` for amounts paid out to a user, rounded down
+pub fn output_round_down(numerator: u128, denominator: u128) -> u128 {
+    if denominator == 0 {
+        return 0;
+    }
+    numerator / denominator
+}
+
+/// Convert a u128 intermediate back to i128, failing instead of wrapping negative
+pub fn u128_to_i128(x: u128) -> Result<i128, SwapTradeError> {
+    i128::try_from(x).map_err(|_| SwapTradeError::AmountOverflow)
+}
+
+/// Constant-product output for `amount_in` after the LP fee.
+/// dy = (y * dx') / (x + dx') where dx' = dx * (1 - fee). Returns 0 for an empty pool.
+pub fn calculate_amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128) -> u128 {
+    if amount_in == 0 || reserve_in == 0 || reserve_out == 0 {
+        return 0;
+    }
+    // Apply fee: amount_in_after_fee = amount_in - fee, with the fee rounded up
+    let fee = fee_round_up(amount_in as i128, LP_FEE_BPS as u32) as u128;
+    let amount_in_after_fee = amount_in.saturating_sub(fee);
+
+    // Constant product formula: (x + dx) * (y - dy) = x * y
+    let numerator = reserve_out.saturating_mul(amount_in_after_fee);
+    let denominator = reserve_in.saturating_add(amount_in_after_fee);
+    output_round_down(numerator, denominator)
+}
+
+/// Input (fee included, rounded up) needed to receive exactly `amount_out`.
+/// Returns None if the pool is empty or cannot supply `amount_out`.
+pub fn calculate_amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128) -> Option<u128> {
+    if amount_out == 0 || reserve_in == 0 || amount_out >= reserve_out {
+        return None;
+    }
+    // dx' = (x * dy) / (y - dy), rounded up, then grossed up for the fee
+    let net_in = reserve_in.saturating_mul(amount_out) / (reserve_out - amount_out) + 1;
+    let fee_factor = 10000 - LP_FEE_BPS;
+    Some((net_in.saturating_mul(10000) + fee_factor - 1) / fee_factor)
+}
+
+/// Round-trip spread in bps for `amount` USDCSIM: the XLM cost of buying `amount`
+/// versus the XLM received for selling `amount` back, both against current reserves.
+/// Captures fees plus price impact on each side. Returns 0 for an empty pool and
+/// 10000 when the pool cannot supply `amount`.
+pub fn round_trip_spread_bps(portfolio: &Portfolio, amount: i128) -> u32 {
+    if amount <= 0 {
+        return 0;
+    }
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM) as u128;
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset()) as u128;
+    if xlm_reserve == 0 || usdc_reserve == 0 {
+        return 0;
+    }
+    let amount = amount as u128;
+
+    let buy_cost = match calculate_amount_in(amount, xlm_reserve, usdc_reserve) {
+        Some(cost) => cost,
+        None => return 10000,
+    };
+    let sell_proceeds = calculate_amount_out(amount, usdc_reserve, xlm_reserve);
+
+    let loss = buy_cost.saturating_sub(sell_proceeds);
+    ((loss * 10000) / buy_cost) as u32
+}
+
+/// Integer square root (floor) via Newton's method
+pub fn integer_sqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = n / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Fee-inclusive input needed to move the pool's spot rate (USDCSIM per XLM, scaled by 1e7)
+/// to `target_rate`. Lowering the rate requires selling XLM, raising it requires selling
+/// USDCSIM; the result is denominated in whichever asset is sold.
+/// Returns 0 for an empty pool, a non-positive target, or a target at or past the current rate.
+pub fn depth_to_rate(portfolio: &Portfolio, target_rate: i128) -> i128 {
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM);
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset());
+    if target_rate <= 0 || xlm_reserve <= 0 || usdc_reserve <= 0 {
+        return 0;
+    }
+    let (x, y, target) = (xlm_reserve as u128, usdc_reserve as u128, target_rate as u128);
+    let k = x.saturating_mul(y);
+
+    // With x * y = k held constant, the rate y / x reaches target when
+    // x' = sqrt(k / target) (selling XLM) or y' = sqrt(k * target) (selling USDCSIM)
+    let net_in = if y.saturating_mul(RATE_PRECISION) > target.saturating_mul(x) {
+        integer_sqrt(k.saturating_mul(RATE_PRECISION) / target).saturating_sub(x)
+    } else {
+        integer_sqrt(k.saturating_mul(target) / RATE_PRECISION).saturating_sub(y)
+    };
+    if net_in == 0 {
+        return 0;
+    }
+
+    // Gross up for the LP fee, which does not enter the reserves
+    let fee_factor = 10000 - LP_FEE_BPS;
+    ((net_in.saturating_mul(10000) + fee_factor - 1) / fee_factor) as i128
+}
+
+/// Where `perform_swap` takes its price from. Every mode is capped at the constant-product
+/// quote, so no swap can pay out more than the reserves allow without shrinking k.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PricingMode {
+    /// Constant-product pricing against pool reserves (default)
+    Reserves,
+    /// Oracle price, capped at the reserve quote; the swap fails if no fresh price is available
+    Oracle,
+    /// Whichever of the two pays the user more within the cap, falling back to reserves without
+    /// an oracle price. Since the cap is the reserve quote itself, this always prices at it.
+    BestOf,
+}
+
+pub fn get_pricing_mode(env: &Env) -> PricingMode {
+    env.storage().instance().get(&symbol_short!("PRC_MODE")).unwrap_or(PricingMode::Reserves)
+}
+
+pub fn set_pricing_mode(env: &Env, mode: PricingMode) {
+    env.storage().instance().set(&symbol_short!("PRC_MODE"), &mode);
+}
+
+/// Maximum age (seconds) of an oracle price used to price a swap; defaults to the general staleness threshold
+pub fn get_swap_oracle_max_age(env: &Env) -> u64 {
+    env.storage().instance().get(&symbol_short!("SWP_AGE")).unwrap_or(STALE_THRESHOLD_SECONDS)
+}
+
+pub fn set_swap_oracle_max_age(env: &Env, seconds: u64) {
+    env.storage().instance().set(&symbol_short!("SWP_AGE"), &seconds);
+}
+
+/// Oracle-priced output for `amount_in` after the LP fee. Returns None when there is no
+/// price younger than the swap max age. The output is not bounded by the reserves; `price_swap` caps it.
+fn oracle_amount_out(env: &Env, from: &Symbol, to: &Symbol, amount_in: u128) -> Option<u128> {
+    let price = get_price_with_staleness_check(env, from.clone(), to.clone(), get_swap_oracle_max_age(env)).ok()?;
+    let fee = fee_round_up(amount_in as i128, LP_FEE_BPS as u32) as u128;
+    Some(output_round_down(amount_in.saturating_sub(fee).saturating_mul(price), PRECISION))
+}
+
+/// Output for `amount_in` (already net of transfer fees) under the current pricing mode,
+/// never more than the k-preserving reserve quote
+fn price_swap(env: &Env, from: &Symbol, to: &Symbol, amount_in: u128, reserve_in: u128, reserve_out: u128) -> u128 {
+    let reserves_out = calculate_amount_out(amount_in, reserve_in, reserve_out);
+    match get_pricing_mode(env) {
+        // The better of the two within the cap is always the cap itself
+        PricingMode::Reserves | PricingMode::BestOf => reserves_out,
+        PricingMode::Oracle => oracle_amount_out(env, from, to, amount_in)
+            .expect("Oracle price unavailable")
+            .min(reserves_out),
+    }
+}
+
+/// Output `perform_swap` would pay for `amount` of `from` at current state, without executing.
+/// Returns 0 when the pool is empty.
+pub fn quote_swap_out(env: &Env, portfolio: &Portfolio, from: &Symbol, to: &Symbol, amount: i128) -> i128 {
+    if amount <= 0 || !has_liquidity(portfolio) {
+        return 0;
+    }
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM) as u128;
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset()) as u128;
+    let (reserve_in, reserve_out) = if *from == symbol_short!("XLM") {
+        (xlm_reserve, usdc_reserve)
+    } else {
+        (usdc_reserve, xlm_reserve)
+    };
+    let amount_received = assets::net_received(env, from, amount) as u128;
+    price_swap(env, from, to, amount_received, reserve_in, reserve_out) as i128
+}
+
+/// Fee rebate (bps) for swaps that move the pool toward its target ratio (0 = disabled)
+pub fn get_rebalance_rebate_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&symbol_short!("REB_BPS")).unwrap_or(0)
+}
+
+pub fn set_rebalance_rebate_bps(env: &Env, bps: u32) {
+    assert!(bps <= 10000, "Rebate must be <= 10000 bps");
+    env.storage().instance().set(&symbol_short!("REB_BPS"), &bps);
+}
+
+/// How far the reserves are from the target ratio, in bps of total pool value.
+/// The target is equal value on both sides at the oracle price, or equal units without one.
+fn imbalance_bps(env: &Env, portfolio: &Portfolio, xlm_reserve: i128, usdc_reserve: i128) -> u128 {
+    let xlm_value = match get_price_with_staleness_check(env, symbol_short!("XLM"), portfolio.stable_symbol(), STALE_THRESHOLD_SECONDS) {
+        Ok(price) => (xlm_reserve as u128).saturating_mul(price) / PRECISION,
+        Err(_) => xlm_reserve as u128,
+    };
+    let usdc_value = usdc_reserve as u128;
+    let total = xlm_value.saturating_add(usdc_value);
+    if total == 0 {
+        return 0;
+    }
+    xlm_value.abs_diff(usdc_value).saturating_mul(10000) / total
+}
+
+/// Whether swapping `amount` (after the swap fee) of `from` leaves the pool closer to its
+/// target ratio than it is now
+pub fn swap_reduces_imbalance(env: &Env, portfolio: &Portfolio, from: &Symbol, to: &Symbol, amount: i128) -> bool {
+    if amount <= 0 || !has_liquidity(portfolio) {
+        return false;
+    }
+    let out = quote_swap_out(env, portfolio, from, to, amount);
+    if out >= output_reserve(portfolio, from) {
+        return false;
+    }
+    let amount_received = assets::net_received(env, from, amount);
+    let into_pool = amount_received - fee_round_up(amount_received, LP_FEE_BPS as u32);
+
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM);
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset());
+    let (xlm_after, usdc_after) = if *from == symbol_short!("XLM") {
+        (xlm_reserve.saturating_add(into_pool), usdc_reserve - out)
+    } else {
+        (xlm_reserve - out, usdc_reserve.saturating_add(into_pool))
+    };
+    imbalance_bps(env, portfolio, xlm_after, usdc_after) < imbalance_bps(env, portfolio, xlm_reserve, usdc_reserve)
+}
+
+/// Fee-free swaps a tier gets each UTC day (0 by default)
+pub fn get_free_swap_allowance(env: &Env, tier: &UserTier) -> u32 {
+    env.storage().instance().get(&(symbol_short!("FREE_SWP"), tier.clone())).unwrap_or(0)
+}
+
+pub fn set_free_swap_allowance(env: &Env, tier: &UserTier, swaps_per_day: u32) {
+    env.storage().instance().set(&(symbol_short!("FREE_SWP"), tier.clone()), &swaps_per_day);
+}
+
+/// Free swaps `user` has used during the current UTC day
+pub fn free_swaps_used_today(env: &Env, user: &Address) -> u32 {
+    let day = env.ledger().timestamp() / SECONDS_PER_DAY;
+    match env.storage().temporary().get::<_, (u64, u32)>(&(user.clone(), symbol_short!("free_used"))) {
+        Some((used_day, used)) if used_day == day => used,
+        _ => 0,
+    }
+}
+
+/// Whether `user` still has a fee-free swap left today under their tier's allowance
+pub fn has_free_swap(env: &Env, portfolio: &Portfolio, user: &Address) -> bool {
+    let tier = portfolio.get_user_tier(env, user.clone());
+    free_swaps_used_today(env, user) < get_free_swap_allowance(env, &tier)
+}
+
+/// Use up one of `user`'s free swaps for today. A single (day, used) entry per user is kept in
+/// temporary storage and overwritten on rollover; it only has to outlive the day it counts.
+pub fn consume_free_swap(env: &Env, user: &Address) {
+    let day = env.ledger().timestamp() / SECONDS_PER_DAY;
+    let used = free_swaps_used_today(env, user);
+    let key = (user.clone(), symbol_short!("free_used"));
+    env.storage().temporary().set(&key, &(day, used + 1));
+    env.storage().temporary().extend_ttl(&key, FREE_SWAP_TTL_LEDGERS, FREE_SWAP_TTL_LEDGERS);
+}
+
+/// Swap fee for `user` on this swap: zero while they have a free swap left today, otherwise
+/// their tier and badge fee less the rebalance rebate when the swap reduces pool imbalance. Floored at zero.
+pub fn effective_swap_fee_bps(env: &Env, portfolio: &Portfolio, user: Address, from: &Symbol, to: &Symbol, amount: i128) -> u32 {
+    if has_free_swap(env, portfolio, &user) {
+        return 0;
+    }
+    paid_swap_fee_bps(env, portfolio, user, from, to, amount)
+}
+
+/// `effective_swap_fee_bps` for a swap that isn't covered by a free swap
+fn paid_swap_fee_bps(env: &Env, portfolio: &Portfolio, user: Address, from: &Symbol, to: &Symbol, amount: i128) -> u32 {
+    let fee_bps = portfolio.swap_fee_bps(env, user);
+    let rebate = get_rebalance_rebate_bps(env);
+    if rebate == 0 {
+        return fee_bps;
+    }
+    let swap_amount = amount - fee_round_up(amount, fee_bps);
+    if swap_reduces_imbalance(env, portfolio, from, to, swap_amount) {
+        fee_bps.saturating_sub(rebate)
+    } else {
+        fee_bps
+    }
+}
+
+/// Reserve on the output side of a swap from `from`. A swap's output must stay strictly
+/// below it; every pricing mode is capped at the constant-product quote, which guarantees this.
+pub fn output_reserve(portfolio: &Portfolio, from: &Symbol) -> i128 {
+    if *from == symbol_short!("XLM") {
+        portfolio.get_liquidity(portfolio.stable_asset())
+    } else {
+        portfolio.get_liquidity(Asset::XLM)
+    }
+}
+
+/// Simulate `user` swapping `amount` along `path` (e.g. [XLM, USDCSIM, XLM]) hop by hop as `swap`
+/// would: each hop pays the user's fee (free swaps included) and moves a copy of the pool, and
+/// the user's trade is recorded on that copy so later hops see their updated tier.
+/// Fails with `InvalidSwapPair` if any hop has no pool and `InsufficientLiquidity` if it is
+/// empty or cannot supply the hop's output.
+pub fn quote_route(env: &Env, portfolio: &Portfolio, path: &Vec<Symbol>, amount: i128, user: Address) -> Result<i128, SwapTradeError> {
+    if path.len() < 2 {
+        return Err(SwapTradeError::InvalidSwapPair);
+    }
+
+    let mut pool = portfolio.clone();
+    let mut amount = amount;
+    let mut free_used = free_swaps_used_today(env, &user);
+    for i in 0..path.len() - 1 {
+        let from = path.get(i).unwrap();
+        let to = path.get(i + 1).unwrap();
+        let from_asset = symbol_to_asset(&pool, &from).ok_or(SwapTradeError::InvalidSwapPair)?;
+        let to_asset = symbol_to_asset(&pool, &to).ok_or(SwapTradeError::InvalidSwapPair)?;
+        if from_asset == to_asset {
+            return Err(SwapTradeError::InvalidSwapPair);
+        }
+        if !has_liquidity(&pool) {
+            return Err(SwapTradeError::InsufficientLiquidity);
+        }
+
+        // Same steps as `swap`: the user's fee on the input, then `perform_swap` pricing and reserve update
+        let tier = pool.get_user_tier(env, user.clone());
+        let fee_bps = if free_used < get_free_swap_allowance(env, &tier) {
+            free_used += 1;
+            0
+        } else {
+            paid_swap_fee_bps(env, &pool, user.clone(), &from, &to, amount)
+        };
+        let swap_amount = amount - fee_round_up(amount, fee_bps);
+        let out = quote_swap_out(env, &pool, &from, &to, swap_amount);
+        if out >= output_reserve(&pool, &from) {
+            return Err(SwapTradeError::InsufficientLiquidity);
+        }
+        let amount_received = assets::net_received(env, &from, swap_amount);
+        let into_pool = amount_received - fee_round_up(amount_received, LP_FEE_BPS as u32);
+
+        move_reserves(&mut pool, &from_asset, into_pool, out);
+        pool.update_stats_on_trade(env, user.clone(), swap_amount);
+        pool.record_trade(env, user.clone());
+        amount = out;
+    }
+    Ok(amount)
+}
+
+/// Everything a swap confirmation screen needs, computed with the same logic as `swap`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapPreview {
+    /// Output the user receives
+    pub expected_out: i128,
+    /// Swap fee charged on the input
+    pub fee_amount: i128,
+    /// Shortfall of `expected_out` against the spot price, in bps
+    pub price_impact_bps: u32,
+    /// Lowest acceptable output after the requested slippage tolerance
+    pub min_received: i128,
+    /// Why the swap would be rejected, if it would; every other field is then 0
+    pub failure: Option<OrderFailure>,
+}
+
+/// Swaps of at least this size are treated as large for anti-sandwich checks (0 = disabled)
+pub fn get_large_swap_threshold(env: &Env) -> i128 {
+    env.storage().instance().get(&symbol_short!("LG_THR")).unwrap_or(0)
+}
+
+pub fn set_large_swap_threshold(env: &Env, threshold: i128) {
+    env.storage().instance().set(&symbol_short!("LG_THR"), &threshold);
+}
+
+/// When set, swaps must carry an explicit nonzero `min_out`
+pub fn requires_slippage_protection(env: &Env) -> bool {
+    env.storage().instance().get(&symbol_short!("REQ_SLIP")).unwrap_or(false)
+}
+
+pub fn set_require_slippage_protection(env: &Env, required: bool) {
+    env.storage().instance().set(&symbol_short!("REQ_SLIP"), &required);
+}
+
+/// Anti-sandwich guard for large swaps. Returns false when `user` already made a large swap
+/// in the opposite direction in the current ledger; otherwise marks this swap and returns true.
+pub fn check_and_mark_large_swap(env: &Env, user: &Address, from: &Symbol, amount: i128) -> bool {
+    let threshold = get_large_swap_threshold(env);
+    if threshold <= 0 || amount < threshold {
+        return true;
+    }
+
+    let ledger = env.ledger().sequence();
+    let key = (user.clone(), symbol_short!("lg_swap"));
+    if let Some((marked_ledger, marked_from)) = env.storage().temporary().get::<_, (u32, Symbol)>(&key) {
+        if marked_ledger == ledger && marked_from != *from {
+            return false;
+        }
+    }
+
+    env.storage().temporary().set(&key, &(ledger, from.clone()));
+    true
+}
+
+/// True when the pool holds a positive reserve on both sides
+pub fn has_liquidity(portfolio: &Portfolio) -> bool {
+    portfolio.get_liquidity(Asset::XLM) > 0
+        && portfolio.get_liquidity(portfolio.stable_asset()) > 0
+}
+
+/// Raw reserves of the pool trading `from` against `to`, in (from, to) order.
+/// The contract holds a single XLM/stable pool, so every other pair returns (0, 0).
+pub fn pair_reserves(portfolio: &Portfolio, from: &Symbol, to: &Symbol) -> (i128, i128) {
+    match (symbol_to_asset(portfolio, from), symbol_to_asset(portfolio, to)) {
+        (Some(from_asset), Some(to_asset)) if from != to => {
+            (portfolio.get_liquidity(from_asset), portfolio.get_liquidity(to_asset))
+        }
+        _ => (0, 0),
+    }
+}
+
+/// Current pool rate (USDCSIM per XLM, scaled by 1e7). Returns 0 when the pool is empty.
+pub fn pool_rate(portfolio: &Portfolio) -> u128 {
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM);
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset());
+    if xlm_reserve <= 0 || usdc_reserve <= 0 {
+        return 0;
+    }
+    (usdc_reserve as u128).saturating_mul(RATE_PRECISION) / (xlm_reserve as u128)
+}
+
+/// Value of an LP deposit in the stable asset: the XLM side is priced at the current
+/// reserve ratio, or for the first deposit at the deposit's own ratio.
+pub fn deposit_value(portfolio: &Portfolio, xlm_amount: i128, usdc_amount: i128) -> i128 {
+    if !has_liquidity(portfolio) {
+        return usdc_amount.saturating_mul(2);
+    }
+    let xlm_value = spot_quote(portfolio, &symbol_short!("XLM"), xlm_amount);
+    usdc_amount.saturating_add(xlm_value)
+}
+
+/// Spot-price output for `amount` at current reserves, ignoring fees and price impact.
+/// Returns 0 when the pool is empty.
+pub fn spot_quote(portfolio: &Portfolio, from: &Symbol, amount: i128) -> i128 {
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM);
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset());
+    if amount <= 0 || xlm_reserve <= 0 || usdc_reserve <= 0 {
+        return 0;
+    }
+    let (reserve_in, reserve_out) = if *from == symbol_short!("XLM") {
+        (xlm_reserve as u128, usdc_reserve as u128)
+    } else {
+        (usdc_reserve as u128, xlm_reserve as u128)
+    };
+    ((amount as u128).saturating_mul(reserve_out) / reserve_in) as i128
+}
+
+/// Store a user's default slippage tolerance in bps
+pub fn set_user_slippage_bps(env: &Env, user: &Address, bps: u32) {
+    assert!(bps <= 10000, "Slippage must be <= 10000 bps");
+    env.storage()
+        .persistent()
+        .set(&(user.clone(), symbol_short!("slip")), &bps);
+}
+
+/// A user's default slippage tolerance in bps, if they have set one
+/// (swaps fall back to `DEFAULT_SLIPPAGE_BPS` otherwise)
+pub fn get_user_slippage_bps(env: &Env, user: &Address) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get(&(user.clone(), symbol_short!("slip")))
+}
+
+/// Add `into_pool` to the reserve of `from_asset` and pay `out` from the other side
+fn move_reserves(portfolio: &mut Portfolio, from_asset: &Asset, into_pool: i128, out: i128) {
+    let to_asset = if *from_asset == Asset::XLM { portfolio.stable_asset() } else { Asset::XLM };
+    portfolio.set_liquidity(from_asset.clone(), portfolio.get_liquidity(from_asset.clone()).saturating_add(into_pool));
+    portfolio.set_liquidity(to_asset.clone(), portfolio.get_liquidity(to_asset).saturating_sub(out));
+}
+
 /// Performs a swap with oracle pricing and slippage protection
 pub fn perform_swap(
     env: &Env,
@@ -51,125 +563,125 @@ pub fn perform_swap(
     to: Symbol,
     amount: i128,
     user: Address,
+) -> i128 {
+    perform_swap_to(env, portfolio, from, to, amount, user.clone(), user)
+}
+
+/// `perform_swap`, crediting the output to `recipient` instead of the user paying the input
+/// Fails with `StalePrice` or `InvalidPrice` if the pair's oracle price can't be trusted.
+/// A pair with no oracle price at all is priced by the pool alone.
+pub fn check_oracle_price(env: &Env, from: &Symbol, to: &Symbol) -> Result<(), ContractError> {
+    match get_price_with_staleness_check(env, from.clone(), to.clone(), STALE_THRESHOLD_SECONDS) {
+        Err(err @ (ContractError::StalePrice | ContractError::InvalidPrice)) => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Admin cap on price impact per swap, in bps (10000 = uncapped)
+fn max_slippage_bps(env: &Env) -> u32 {
+    env.storage().instance().get(&symbol_short!("MAX_SLIP")).unwrap_or(10000u32)
+}
+
+/// How far `actual_out` falls short of the fee-less constant-product output, in bps
+fn slippage_bps(reserve_in: u128, reserve_out: u128, amount_in: u128, actual_out: u128) -> u128 {
+    let theoretical_out = reserve_out.saturating_mul(amount_in) / reserve_in.saturating_add(amount_in);
+    if theoretical_out == 0 {
+        return 0;
+    }
+    (theoretical_out.saturating_sub(actual_out) * 10000) / theoretical_out
+}
+
+/// Whether swapping `amount` of `from` would trip the `MAX_SLIP` check in `perform_swap_to`
+pub fn exceeds_max_slippage(env: &Env, portfolio: &Portfolio, from: &Symbol, to: &Symbol, amount: i128) -> bool {
+    if amount <= 0 || !has_liquidity(portfolio) {
+        return false;
+    }
+    let xlm_reserve = portfolio.get_liquidity(Asset::XLM) as u128;
+    let usdc_reserve = portfolio.get_liquidity(portfolio.stable_asset()) as u128;
+    let (reserve_in, reserve_out) = if *from == symbol_short!("XLM") {
+        (xlm_reserve, usdc_reserve)
+    } else {
+        (usdc_reserve, xlm_reserve)
+    };
+    let amount_in = assets::net_received(env, from, amount) as u128;
+    let actual_out = price_swap(env, from, to, amount_in, reserve_in, reserve_out);
+    slippage_bps(reserve_in, reserve_out, amount_in, actual_out) > max_slippage_bps(env) as u128
+}
+
+pub fn perform_swap_to(
+    env: &Env,
+    portfolio: &mut Portfolio,
+    from: Symbol,
+    to: Symbol,
+    amount: i128,
+    user: Address,
+    recipient: Address,
 ) -> i128 {
     assert!(amount > 0, "Amount must be positive");
     assert!(from != to, "Tokens must be different");
 
-    let from_asset = symbol_to_asset(&from).expect("Invalid from token");
-    let to_asset = symbol_to_asset(&to).expect("Invalid to token");
-
-    // 1. Get Price (Default to 1:1 if not set, to support existing tests/defaults, or panic?)
-    // Requirement: "Currently using hardcoded 1:1 (unrealistic)".
-    // Implementation: Try Oracle, fallback to 1:1 if not set (with warning logic if possible, but here just fallback)
-    // BUT we need to support "Stale Price" error.
-    // So:
-    // - If Price Set & Valid -> Use it.
-    // - If Price Set & Stale -> Panic/Error.
-    // - If Price NOT Set -> Use 1:1 (Legacy/Default).
-    
-    let price = match get_price_with_staleness_check(env, from.clone(), to.clone()) {
-        Ok(p) => p,
+    let from_asset = symbol_to_asset(portfolio, &from).expect("Invalid from token");
+    let to_asset = symbol_to_asset(portfolio, &to).expect("Invalid to token");
+
+    // 1. Reject stale or invalid oracle prices; the output itself is priced by the pool
+    match check_oracle_price(env, &from, &to) {
         Err(ContractError::StalePrice) => panic!("Oracle price is stale"),
-        Err(ContractError::InvalidPrice) => panic!("Oracle price is invalid"),
-        Err(ContractError::PriceNotSet) => PRECISION, // Fallback to 1:1
-        _ => PRECISION,
-    };
+        Err(_) => panic!("Oracle price is invalid"),
+        Ok(()) => {}
+    }
 
     // 2. Get current pool liquidity (from LP pool)
     let xlm_liquidity = portfolio.get_liquidity(Asset::XLM);
-    let usdc_liquidity = portfolio.get_liquidity(Asset::Custom(symbol_short!("USDCSIM")));
+    let usdc_liquidity = portfolio.get_liquidity(portfolio.stable_asset());
 
     // 3. Calculate swap output using constant product AMM formula: x * y = k
     // With 0.3% fee: amount_out = (y * amount_in * (1 - fee)) / (x + amount_in * (1 - fee))
-    let amount_u128 = amount as u128;
+    // Fee-on-transfer tokens deliver less than the user sends, so price what the pool receives
+    let amount_received = assets::net_received(env, &from, amount);
+    let amount_u128 = amount_received as u128;
     let (reserve_in, reserve_out) = if from_asset == Asset::XLM {
         (xlm_liquidity as u128, usdc_liquidity as u128)
     } else {
         (usdc_liquidity as u128, xlm_liquidity as u128)
     };
 
-    let actual_out = if reserve_in > 0 && reserve_out > 0 {
-        // Apply fee: amount_in_after_fee = amount_in * (1 - fee_bps / 10000)
-        let amount_in_after_fee = (amount_u128 * (10000 - LP_FEE_BPS)) / 10000;
-        
-        // Constant product formula: (x + dx) * (y - dy) = x * y
-        // dy = (y * dx) / (x + dx)
-        let numerator = reserve_out.saturating_mul(amount_in_after_fee);
-        let denominator = reserve_in.saturating_add(amount_in_after_fee);
-        
-        if denominator == 0 {
-            panic!("Division by zero in AMM calculation");
-        }
-        
-        numerator / denominator
-    } else {
-        // If no liquidity, use oracle price (fallback)
-        let price = match get_price_with_staleness_check(env, from.clone(), to.clone()) {
-            Ok(p) => p,
-            Err(ContractError::StalePrice) => panic!("Oracle price is stale"),
-            Err(ContractError::InvalidPrice) => panic!("Oracle price is invalid"),
-            Err(ContractError::PriceNotSet) => PRECISION, // Fallback to 1:1
-            _ => PRECISION,
-        };
-        (amount_u128 * price) / PRECISION
-    };
+    // An empty side would price against nothing; callers should check `has_liquidity` first
+    assert!(reserve_in > 0 && reserve_out > 0, "Insufficient liquidity");
+
+    let actual_out = price_swap(env, &from, &to, amount_u128, reserve_in, reserve_out);
+
+    assert!(actual_out < reserve_out, "Insufficient liquidity");
 
     let out_amount = actual_out as i128;
     assert!(out_amount > 0, "Output amount must be positive");
 
     // 4. Calculate fee amount (0.3% of input)
-    let fee_amount = (amount_u128 * LP_FEE_BPS) / 10000;
-    let fee_amount_i128 = fee_amount as i128;
+    let fee_amount_i128 = fee_round_up(amount_received, LP_FEE_BPS as u32);
 
     // 5. Check slippage protection
-    let theoretical_out = if reserve_in > 0 && reserve_out > 0 {
-        // Theoretical output without fee
-        let numerator = reserve_out.saturating_mul(amount_u128);
-        let denominator = reserve_in.saturating_add(amount_u128);
-        if denominator == 0 {
-            amount_u128 // Fallback
-        } else {
-            numerator / denominator
-        }
-    } else {
-        amount_u128 // Fallback to 1:1
-    };
-
-    let max_slip = env.storage().instance().get(&symbol_short!("MAX_SLIP")).unwrap_or(10000u32);
-    if theoretical_out > 0 {
-        let slippage_bps = ((theoretical_out - actual_out) * 10000) / theoretical_out;
-        if slippage_bps > max_slip as u128 {
-            panic!("Slippage exceeded: {} bps > {} bps", slippage_bps, max_slip);
-        }
+    let max_slip = max_slippage_bps(env);
+    let slippage_bps = slippage_bps(reserve_in, reserve_out, amount_u128, actual_out);
+    if slippage_bps > max_slip as u128 {
+        panic!("Slippage exceeded: {} bps > {} bps", slippage_bps, max_slip);
     }
 
-    // 6. Update Portfolio (User Balances) - transfer from user
-    portfolio.transfer_asset(env, from_asset.clone(), to_asset.clone(), user.clone(), amount);
-    // 4. Update Portfolio (User Balances)
+    // 6. Update Portfolio (User Balances)
     // Debit input Amount
     portfolio.debit(env, from_asset.clone(), user.clone(), amount);
-    // Credit output Amount (calculated by AMM/Oracle)
-    portfolio.credit(env, to_asset.clone(), user.clone(), out_amount);
+    // Credit output Amount (calculated by AMM)
+    portfolio.credit(env, to_asset.clone(), recipient, out_amount);
+    portfolio.update_stats_on_trade(env, user.clone(), amount);
     
     // 7. Update Pool Liquidity using constant product AMM
     // Add input amount (minus fee) to reserve_in, subtract output from reserve_out
-    if reserve_in > 0 && reserve_out > 0 {
-        let amount_in_after_fee = amount - fee_amount_i128;
-        
-        if from_asset == Asset::XLM {
-            portfolio.set_liquidity(Asset::XLM, xlm_liquidity.saturating_add(amount_in_after_fee));
-            portfolio.set_liquidity(Asset::Custom(symbol_short!("USDCSIM")), usdc_liquidity.saturating_sub(out_amount));
-        } else {
-            portfolio.set_liquidity(Asset::Custom(symbol_short!("USDCSIM")), usdc_liquidity.saturating_add(amount_in_after_fee));
-            portfolio.set_liquidity(Asset::XLM, xlm_liquidity.saturating_sub(out_amount));
-        }
-    }
+    let amount_in_after_fee = amount_received - fee_amount_i128;
+    move_reserves(portfolio, &from_asset, amount_in_after_fee, out_amount);
 
     // 8. Collect and attribute fees to LPs
     if fee_amount_i128 > 0 {
-        portfolio.add_lp_fees(fee_amount_i128);
-        // Fees are accumulated and can be distributed proportionally to LPs based on their LP token share
-        // This is tracked in lp_fees_accumulated for future distribution
+        portfolio.add_lp_fees(from_asset.clone(), fee_amount_i128);
+        // Fees are accumulated per input asset and can be distributed proportionally to LPs
+        // based on their LP token share
     }
 
     out_amount